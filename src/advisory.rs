@@ -0,0 +1,57 @@
+//! Functions for looking up security advisories.
+
+use crate::document::ExternalReference;
+use cargo_metadata::Package;
+
+/// The RustSec advisory database.
+pub struct AdvisoryDatabase(
+    /// The database.
+    rustsec::Database,
+);
+
+impl AdvisoryDatabase {
+    /// Fetch the latest copy of the advisory database.
+    ///
+    /// Failing to fetch the database isn't fatal, so failures are logged and `None`
+    /// is returned instead.
+    pub fn fetch() -> Option<Self> {
+        log::info!(target: "cargo_spdx", "fetching the RustSec advisory database");
+
+        match rustsec::Database::fetch() {
+            Ok(db) => Some(AdvisoryDatabase(db)),
+            Err(e) => {
+                log::warn!(
+                    target: "cargo_spdx",
+                    "failed to fetch the RustSec advisory database, advisories won't be included: {}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Get references to the advisories for vulnerabilities affecting the package.
+    pub fn references(&self, package: &Package) -> Vec<ExternalReference> {
+        self.0
+            .iter()
+            // Withdrawn and informational advisories don't describe vulnerabilities.
+            .filter(|advisory| advisory.metadata.withdrawn.is_none())
+            .filter(|advisory| advisory.metadata.informational.is_none())
+            .filter(|advisory| advisory.metadata.package.as_str() == package.name)
+            .filter(|advisory| advisory.versions.is_vulnerable(&package.version))
+            .map(|advisory| {
+                log::info!(
+                    target: "cargo_spdx",
+                    "package '{}' is affected by advisory {}",
+                    package.name,
+                    advisory.metadata.id
+                );
+
+                ExternalReference::advisory(format!(
+                    "https://rustsec.org/advisories/{}.html",
+                    advisory.metadata.id
+                ))
+            })
+            .collect()
+    }
+}
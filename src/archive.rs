@@ -0,0 +1,215 @@
+//! A minimal writer for tar archives, enough to bundle documents together.
+
+use std::io::{self, Write};
+
+/// The size of the blocks a tar archive is made of.
+const BLOCK_SIZE: usize = 512;
+
+/// The longest name an entry can have in its own header.
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Writes regular files into a ustar archive.
+///
+/// Names too long for the header are given in a pax extended header before the entry. Names
+/// are never split into the ustar prefix field, since documents aren't in directories.
+#[derive(Debug)]
+pub struct TarWriter<W> {
+    /// Where the archive is written.
+    inner: W,
+}
+
+impl<W: Write> TarWriter<W> {
+    /// Start writing an archive.
+    pub fn new(inner: W) -> Self {
+        TarWriter { inner }
+    }
+
+    /// Add a file to the archive, modified at `mtime` seconds since the Unix epoch.
+    pub fn append(&mut self, name: &str, contents: &[u8], mtime: u64) -> io::Result<()> {
+        if name.len() > MAX_NAME_LENGTH {
+            let record = pax_record("path", name);
+            self.write_entry(b"././@PaxHeader", record.as_bytes(), mtime, b'x')?;
+        }
+
+        // Readers which understand pax headers use the full name from them instead.
+        let name = &name.as_bytes()[..name.len().min(MAX_NAME_LENGTH)];
+        self.write_entry(name, contents, mtime, b'0')
+    }
+
+    /// Finish the archive, returning the writer it was written to.
+    pub fn finish(mut self) -> io::Result<W> {
+        // An archive ends with two empty blocks.
+        self.inner.write_all(&[0; BLOCK_SIZE * 2])?;
+        Ok(self.inner)
+    }
+
+    /// Write an entry of type `typeflag`, its header followed by its contents.
+    fn write_entry(
+        &mut self,
+        name: &[u8],
+        contents: &[u8],
+        mtime: u64,
+        typeflag: u8,
+    ) -> io::Result<()> {
+        let mut header = [0; BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name);
+        write_octal(&mut header[100..108], 0o644)?;
+        write_octal(&mut header[108..116], 0)?;
+        write_octal(&mut header[116..124], 0)?;
+        write_octal(&mut header[124..136], contents.len() as u64)?;
+        write_octal(&mut header[136..148], mtime)?;
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        // The checksum is calculated with its own field filled with spaces.
+        header[148..156].fill(b' ');
+        let checksum = header.iter().map(|b| u64::from(*b)).sum();
+        write_octal(&mut header[148..155], checksum)?;
+
+        self.inner.write_all(&header)?;
+        self.inner.write_all(contents)?;
+
+        // Entries are padded out to a whole number of blocks.
+        let padding = (BLOCK_SIZE - contents.len() % BLOCK_SIZE) % BLOCK_SIZE;
+        self.inner.write_all(&[0; BLOCK_SIZE][..padding])
+    }
+}
+
+/// Format a pax extended header record, like "30 path=a-very-long-name.json\n".
+///
+/// The length at the start counts every byte of the record, its own digits included.
+fn pax_record(key: &str, value: &str) -> String {
+    let rest = format!(" {}={}\n", key, value);
+    let mut length = rest.len();
+
+    // Adding the digits of the length can make it a digit longer, so repeat until it's stable.
+    loop {
+        let total = rest.len() + length.to_string().len();
+
+        if total == length {
+            break;
+        }
+
+        length = total;
+    }
+
+    format!("{}{}", length, rest)
+}
+
+/// Write a number into a header field as zero-padded octal, ending with a NUL.
+fn write_octal(field: &mut [u8], value: u64) -> io::Result<()> {
+    let width = field.len() - 1;
+    let digits = format!("{:0width$o}", value, width = width);
+
+    if digits.len() > width {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is too large for an archive header", value),
+        ));
+    }
+
+    field[..width].copy_from_slice(digits.as_bytes());
+    field[width] = 0;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write an archive of the given entries, all modified at the Unix epoch.
+    fn archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = TarWriter::new(Vec::new());
+
+        for (name, contents) in entries {
+            archive.append(name, contents, 0).unwrap();
+        }
+
+        archive.finish().unwrap()
+    }
+
+    /// Read a NUL-terminated octal header field.
+    fn read_octal(field: &[u8]) -> u64 {
+        let digits = std::str::from_utf8(field).unwrap();
+        u64::from_str_radix(digits.trim_end_matches(&['\0', ' '][..]), 8).unwrap()
+    }
+
+    /// Read the name of an entry from its header.
+    fn read_name(header: &[u8]) -> &str {
+        let name = &header[..MAX_NAME_LENGTH];
+        let end = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+        std::str::from_utf8(&name[..end]).unwrap()
+    }
+
+    #[test]
+    fn headers_have_valid_checksums() {
+        let archive = archive(&[("fixture.spdx.json", b"{}")]);
+        let mut header = archive[..BLOCK_SIZE].to_vec();
+
+        let recorded = read_octal(&header[148..156]);
+        header[148..156].fill(b' ');
+        let expected = header.iter().map(|b| u64::from(*b)).sum::<u64>();
+
+        assert_eq!(recorded, expected);
+        assert_eq!(read_name(&header), "fixture.spdx.json");
+        assert_eq!(read_octal(&header[124..136]), 2);
+        assert_eq!(header[156], b'0');
+        assert_eq!(&header[257..265], b"ustar\000");
+    }
+
+    #[test]
+    fn contents_are_padded_to_whole_blocks() {
+        let archive = archive(&[("first", b"hello"), ("second", &[b'x'; BLOCK_SIZE])]);
+
+        // Each entry is a header, then its contents padded out to a whole number of blocks.
+        assert_eq!(archive.len(), BLOCK_SIZE * 6);
+        assert_eq!(&archive[BLOCK_SIZE..BLOCK_SIZE + 5], b"hello");
+        assert!(archive[BLOCK_SIZE + 5..BLOCK_SIZE * 2]
+            .iter()
+            .all(|b| *b == 0));
+        assert_eq!(read_name(&archive[BLOCK_SIZE * 2..]), "second");
+    }
+
+    #[test]
+    fn archives_end_with_two_empty_blocks() {
+        let empty = archive(&[]);
+        assert_eq!(empty, vec![0; BLOCK_SIZE * 2]);
+
+        let archive = archive(&[("fixture.spdx.json", b"{}")]);
+        assert_eq!(archive.len(), BLOCK_SIZE * 4);
+        assert!(archive[BLOCK_SIZE * 2..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn long_names_are_given_in_pax_headers() {
+        let name = format!("SPDXRef-{}-1.0.0.spdx.json", "a".repeat(120));
+        let archive = archive(&[(&name, b"{}")]);
+
+        // The pax header comes first, holding the full name.
+        let pax = &archive[..BLOCK_SIZE];
+        assert_eq!(pax[156], b'x');
+
+        let size = read_octal(&pax[124..136]) as usize;
+        let record = std::str::from_utf8(&archive[BLOCK_SIZE..BLOCK_SIZE + size]).unwrap();
+        assert_eq!(record, pax_record("path", &name));
+        assert_eq!(record.len().to_string(), record.split(' ').next().unwrap());
+        assert!(record.ends_with(&format!(" path={}\n", name)));
+
+        // The entry itself follows, with its name cut down to fit its header.
+        let header = &archive[BLOCK_SIZE * 2..BLOCK_SIZE * 3];
+        assert_eq!(header[156], b'0');
+        assert_eq!(read_name(header), &name[..MAX_NAME_LENGTH]);
+        assert_eq!(&archive[BLOCK_SIZE * 3..BLOCK_SIZE * 3 + 2], b"{}");
+    }
+
+    #[test]
+    fn pax_record_lengths_count_their_own_digits() {
+        assert_eq!(pax_record("k", "v"), "6 k=v\n");
+
+        // The rest of this record is 99 bytes, so its length rolls over to three digits.
+        let record = pax_record("k", &"v".repeat(95));
+        assert_eq!(record.len(), 102);
+        assert!(record.starts_with("102 "));
+    }
+}
@@ -0,0 +1,564 @@
+//! Functions for reading the dependency information `cargo auditable` embeds in binaries.
+
+use anyhow::{anyhow, Result};
+use flate2::read::ZlibDecoder;
+use serde::Deserialize;
+use std::fs;
+use std::io::Read;
+use std::ops::Not as _;
+use std::path::Path;
+
+/// The name of the section `cargo auditable` stores the dependency information in.
+const SECTION_NAME: &[u8] = b".dep-v0";
+
+/// The most the dependency information may decompress to, to guard against zip bombs.
+const MAX_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The dependency information embedded in a binary.
+#[derive(Debug, Deserialize)]
+pub struct AuditableInfo {
+    /// The packages compiled into the binary.
+    pub packages: Vec<AuditablePackage>,
+}
+
+/// A package compiled into a binary.
+#[derive(Debug, Deserialize)]
+pub struct AuditablePackage {
+    /// The name of the package.
+    pub name: String,
+    /// The version of the package.
+    pub version: String,
+    /// Where the package came from: 'crates.io', 'git', 'local', or 'registry'.
+    pub source: String,
+    /// Whether the package is only needed to build the binary: 'build' or 'runtime'.
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// The indices of the package's dependencies in the list of packages.
+    #[serde(default)]
+    pub dependencies: Vec<usize>,
+    /// Whether the package is the root package the binary was built from.
+    #[serde(default)]
+    pub root: bool,
+}
+
+impl AuditableInfo {
+    /// Read the dependency information embedded in the binary at `path`.
+    ///
+    /// ELF, PE, and 64-bit Mach-O binaries are supported.
+    pub fn read(path: &Path) -> Result<Self> {
+        log::info!(
+            target: "cargo_spdx",
+            "reading dependency information from '{}'",
+            path.display()
+        );
+
+        let bytes = fs::read(path)?;
+        let section = find_section(&bytes)?.ok_or_else(|| {
+            anyhow!(
+                "'{}' has no dependency information; build it with `cargo auditable`",
+                path.display()
+            )
+        })?;
+
+        let mut json = Vec::new();
+        ZlibDecoder::new(section)
+            .take(MAX_DECOMPRESSED_SIZE)
+            .read_to_end(&mut json)?;
+
+        let info: AuditableInfo = serde_json::from_slice(&json)?;
+
+        if let Some(index) = info
+            .packages
+            .iter()
+            .flat_map(|package| &package.dependencies)
+            .find(|index| **index >= info.packages.len())
+        {
+            return Err(anyhow!(
+                "dependency information refers to missing package {}",
+                index
+            ));
+        }
+
+        Ok(info)
+    }
+
+    /// Get the index of the root package, the first package if none is marked as the root.
+    pub fn root(&self) -> Option<usize> {
+        self.packages
+            .iter()
+            .position(|package| package.root)
+            .or_else(|| self.packages.is_empty().not().then(|| 0))
+    }
+}
+
+/// Find the contents of the dependency information section of a binary, if it has one.
+fn find_section(bytes: &[u8]) -> Result<Option<&[u8]>> {
+    match bytes.get(..4) {
+        Some([0x7f, b'E', b'L', b'F']) => find_elf_section(bytes),
+        Some([b'M', b'Z', _, _]) => find_pe_section(bytes),
+        Some([0xcf, 0xfa, 0xed, 0xfe]) => find_mach_o_section(bytes),
+        _ => Err(anyhow!("not an ELF, PE, or 64-bit Mach-O binary")),
+    }
+}
+
+/// Find a section of an ELF binary by name.
+fn find_elf_section(bytes: &[u8]) -> Result<Option<&[u8]>> {
+    let is_64 = read_u8(bytes, 4)? == 2;
+    let reader = Reader {
+        bytes,
+        big_endian: read_u8(bytes, 5)? == 2,
+    };
+
+    let (section_offset, entry_size, count, names_index) = if is_64 {
+        (
+            reader.u64(0x28)?,
+            reader.u16(0x3a)?,
+            reader.u16(0x3c)?,
+            reader.u16(0x3e)?,
+        )
+    } else {
+        (
+            u64::from(reader.u32(0x20)?),
+            reader.u16(0x2e)?,
+            reader.u16(0x30)?,
+            reader.u16(0x32)?,
+        )
+    };
+
+    // Get the name offset, data offset, and data size of a section header.
+    let header = |index: u16| -> Result<(u64, u64, u64)> {
+        let at = entry_offset(
+            to_usize(section_offset)?,
+            usize::from(index),
+            usize::from(entry_size),
+        )?;
+
+        if is_64 {
+            Ok((
+                u64::from(reader.u32(at)?),
+                reader.u64(offset_by(at, 0x18)?)?,
+                reader.u64(offset_by(at, 0x20)?)?,
+            ))
+        } else {
+            Ok((
+                u64::from(reader.u32(at)?),
+                u64::from(reader.u32(offset_by(at, 0x10)?)?),
+                u64::from(reader.u32(offset_by(at, 0x14)?)?),
+            ))
+        }
+    };
+
+    let (_, names_offset, names_size) = header(names_index)?;
+    let names = slice(bytes, names_offset, names_size)?;
+
+    for index in 0..count {
+        let (name_offset, offset, size) = header(index)?;
+        let name = names
+            .get(to_usize(name_offset)?..)
+            .and_then(|name| name.split(|b| *b == 0).next())
+            .unwrap_or_default();
+
+        if name == SECTION_NAME {
+            return Ok(Some(slice(bytes, offset, size)?));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Find a section of a PE binary by name.
+fn find_pe_section(bytes: &[u8]) -> Result<Option<&[u8]>> {
+    let reader = Reader {
+        bytes,
+        big_endian: false,
+    };
+
+    let pe_offset = to_usize(u64::from(reader.u32(0x3c)?))?;
+
+    if reader.array(pe_offset)? != *b"PE\0\0" {
+        return Err(anyhow!("not a PE binary"));
+    }
+
+    let count = reader.u16(offset_by(pe_offset, 6)?)?;
+    let optional_header_size = reader.u16(offset_by(pe_offset, 20)?)?;
+    let table_offset = offset_by(offset_by(pe_offset, 24)?, usize::from(optional_header_size))?;
+
+    for index in 0..usize::from(count) {
+        let at = entry_offset(table_offset, index, 40)?;
+        let name = slice(bytes, at as u64, 8)?;
+        let name = name.split(|b| *b == 0).next().unwrap_or_default();
+
+        if name == SECTION_NAME {
+            // The raw data is padded, so the section may be smaller.
+            let size = reader
+                .u32(offset_by(at, 8)?)?
+                .min(reader.u32(offset_by(at, 16)?)?);
+            let offset = reader.u32(offset_by(at, 20)?)?;
+            return Ok(Some(slice(bytes, offset.into(), size.into())?));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Find a section of a 64-bit Mach-O binary by name.
+fn find_mach_o_section(bytes: &[u8]) -> Result<Option<&[u8]>> {
+    /// The load command describing a 64-bit segment and its sections.
+    const LC_SEGMENT_64: u32 = 0x19;
+
+    let reader = Reader {
+        bytes,
+        big_endian: false,
+    };
+
+    let count = reader.u32(16)?;
+    let mut at = 32;
+
+    for _ in 0..count {
+        let command = reader.u32(at)?;
+        let command_size = to_usize(u64::from(reader.u32(offset_by(at, 4)?)?))?;
+
+        if command == LC_SEGMENT_64 {
+            let sections = reader.u32(offset_by(at, 64)?)?;
+
+            for index in 0..to_usize(u64::from(sections))? {
+                let section = entry_offset(offset_by(at, 72)?, index, 80)?;
+                let name = slice(bytes, section as u64, 16)?;
+                let name = name.split(|b| *b == 0).next().unwrap_or_default();
+
+                if name == SECTION_NAME {
+                    let size = reader.u64(offset_by(section, 40)?)?;
+                    let offset = reader.u32(offset_by(section, 48)?)?;
+                    return Ok(Some(slice(bytes, offset.into(), size)?));
+                }
+            }
+        }
+
+        if command_size == 0 {
+            return Err(anyhow!("malformed Mach-O load command"));
+        }
+
+        at = offset_by(at, command_size)?;
+    }
+
+    Ok(None)
+}
+
+/// Reads integers out of a binary with its byte order.
+#[derive(Debug, Clone, Copy)]
+struct Reader<'b> {
+    /// The contents of the binary.
+    bytes: &'b [u8],
+    /// Whether integers are big-endian.
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    /// Read a 16-bit integer at an offset.
+    fn u16(&self, at: usize) -> Result<u16> {
+        let bytes = self.array(at)?;
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    /// Read a 32-bit integer at an offset.
+    fn u32(&self, at: usize) -> Result<u32> {
+        let bytes = self.array(at)?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    /// Read a 64-bit integer at an offset.
+    fn u64(&self, at: usize) -> Result<u64> {
+        let bytes = self.array(at)?;
+        Ok(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    /// Read a fixed number of bytes at an offset.
+    fn array<const N: usize>(&self, at: usize) -> Result<[u8; N]> {
+        self.bytes
+            .get(at..offset_by(at, N)?)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| anyhow!("binary is truncated"))
+    }
+}
+
+/// Read a byte at an offset.
+fn read_u8(bytes: &[u8], at: usize) -> Result<u8> {
+    bytes
+        .get(at)
+        .copied()
+        .ok_or_else(|| anyhow!("binary is truncated"))
+}
+
+/// Get the bytes of a binary at an offset.
+fn slice(bytes: &[u8], offset: u64, size: u64) -> Result<&[u8]> {
+    let start = to_usize(offset)?;
+    let end = start
+        .checked_add(to_usize(size)?)
+        .ok_or_else(|| anyhow!("binary is truncated"))?;
+
+    bytes
+        .get(start..end)
+        .ok_or_else(|| anyhow!("binary is truncated"))
+}
+
+/// Add to an offset into a binary, failing rather than overflowing, since offsets read from a
+/// malformed binary may be anything.
+fn offset_by(at: usize, by: usize) -> Result<usize> {
+    at.checked_add(by)
+        .ok_or_else(|| anyhow!("binary is truncated"))
+}
+
+/// Get the offset of the entry at `index` in a table of entries `size` bytes long starting at
+/// `table`, failing rather than overflowing.
+fn entry_offset(table: usize, index: usize, size: usize) -> Result<usize> {
+    index
+        .checked_mul(size)
+        .and_then(|at| at.checked_add(table))
+        .ok_or_else(|| anyhow!("binary is truncated"))
+}
+
+/// Convert an offset or size read from a binary to a `usize`.
+fn to_usize(value: u64) -> Result<usize> {
+    usize::try_from(value).map_err(|_| anyhow!("binary is too large"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    /// The dependency information in each fixture binary.
+    const INFO: &str = r#"{"packages":[
+        {"name":"fixture","version":"0.1.0","source":"local","dependencies":[1],"root":true},
+        {"name":"serde","version":"1.0.0","source":"crates.io"}
+    ]}"#;
+
+    /// Compress the dependency information the way `cargo auditable` does.
+    fn compressed() -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        // PANIC SAFETY: Writing to a vector can't fail.
+        encoder.write_all(INFO.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Write little-endian bytes into a binary at an offset, growing it as needed.
+    fn put(bytes: &mut Vec<u8>, at: usize, value: &[u8]) {
+        if bytes.len() < at + value.len() {
+            bytes.resize(at + value.len(), 0);
+        }
+        bytes[at..at + value.len()].copy_from_slice(value);
+    }
+
+    /// A 64-bit little-endian ELF binary with a null section, the section names, and the
+    /// dependency information, whose section headers come last.
+    fn elf() -> Vec<u8> {
+        let data = compressed();
+        let names = b"\0.shstrtab\0.dep-v0\0";
+        let names_offset = 64 + data.len();
+        let headers_offset = names_offset + names.len();
+
+        let mut bytes = Vec::new();
+        put(&mut bytes, 0, &[0x7f, b'E', b'L', b'F', 2, 1, 1]);
+        put(&mut bytes, 0x28, &(headers_offset as u64).to_le_bytes());
+        put(&mut bytes, 0x3a, &64u16.to_le_bytes());
+        put(&mut bytes, 0x3c, &3u16.to_le_bytes());
+        put(&mut bytes, 0x3e, &1u16.to_le_bytes());
+        put(&mut bytes, 64, &data);
+        put(&mut bytes, names_offset, names);
+
+        for (index, (name, offset, size)) in [
+            (0u32, 0, 0),
+            (1, names_offset, names.len()),
+            (11, 64, data.len()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let at = headers_offset + index * 64;
+            put(&mut bytes, at, &name.to_le_bytes());
+            put(&mut bytes, at + 0x18, &(offset as u64).to_le_bytes());
+            put(&mut bytes, at + 0x20, &(size as u64).to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// A PE binary with a code section and the dependency information, whose raw size is padded
+    /// past the end of the data.
+    fn pe() -> Vec<u8> {
+        let data = compressed();
+        let pe_offset = 0x40;
+        let table_offset = pe_offset + 24;
+        let data_offset = table_offset + 2 * 40;
+
+        let mut bytes = Vec::new();
+        put(&mut bytes, 0, b"MZ");
+        put(&mut bytes, 0x3c, &(pe_offset as u32).to_le_bytes());
+        put(&mut bytes, pe_offset, b"PE\0\0");
+        put(&mut bytes, pe_offset + 6, &2u16.to_le_bytes());
+        put(&mut bytes, pe_offset + 20, &0u16.to_le_bytes());
+
+        put(&mut bytes, table_offset, b".text\0\0\0");
+        let at = table_offset + 40;
+        put(&mut bytes, at, b".dep-v0\0");
+        put(&mut bytes, at + 8, &(data.len() as u32).to_le_bytes());
+        put(&mut bytes, at + 16, &(data.len() as u32 + 16).to_le_bytes());
+        put(&mut bytes, at + 20, &(data_offset as u32).to_le_bytes());
+        put(&mut bytes, data_offset, &data);
+
+        bytes
+    }
+
+    /// A 64-bit Mach-O binary with an unrelated load command, then a segment holding the
+    /// dependency information.
+    fn mach_o() -> Vec<u8> {
+        let data = compressed();
+        let segment = 32 + 16;
+        let data_offset = segment + 72 + 80;
+
+        let mut bytes = Vec::new();
+        put(&mut bytes, 0, &[0xcf, 0xfa, 0xed, 0xfe]);
+        put(&mut bytes, 16, &2u32.to_le_bytes());
+
+        put(&mut bytes, 32, &0x2u32.to_le_bytes());
+        put(&mut bytes, 36, &16u32.to_le_bytes());
+
+        put(&mut bytes, segment, &0x19u32.to_le_bytes());
+        put(&mut bytes, segment + 4, &(72u32 + 80).to_le_bytes());
+        put(&mut bytes, segment + 64, &1u32.to_le_bytes());
+        put(&mut bytes, segment + 72, b".dep-v0");
+        put(
+            &mut bytes,
+            segment + 72 + 40,
+            &(data.len() as u64).to_le_bytes(),
+        );
+        put(
+            &mut bytes,
+            segment + 72 + 48,
+            &(data_offset as u32).to_le_bytes(),
+        );
+        put(&mut bytes, data_offset, &data);
+
+        bytes
+    }
+
+    #[test]
+    fn section_is_found_in_each_format() {
+        for bytes in [elf(), pe(), mach_o()] {
+            assert_eq!(find_section(&bytes).unwrap(), Some(&compressed()[..]));
+        }
+    }
+
+    #[test]
+    fn big_endian_elf_sections_are_found() {
+        let mut bytes = elf();
+        bytes[5] = 2;
+
+        for at in [0x28, 0x3a, 0x3c, 0x3e] {
+            let width = if at == 0x28 { 8 } else { 2 };
+            bytes[at..at + width].reverse();
+        }
+
+        let headers = u64::from_be_bytes(bytes[0x28..0x30].try_into().unwrap()) as usize;
+        for index in 0..3 {
+            let at = headers + index * 64;
+            bytes[at..at + 4].reverse();
+            bytes[at + 0x18..at + 0x20].reverse();
+            bytes[at + 0x20..at + 0x28].reverse();
+        }
+
+        assert_eq!(find_section(&bytes).unwrap(), Some(&compressed()[..]));
+    }
+
+    #[test]
+    fn binary_without_the_section_has_none() {
+        let mut bytes = elf();
+        let names = 64 + compressed().len();
+        put(&mut bytes, names + 11, b".other\0");
+
+        assert_eq!(find_section(&bytes).unwrap(), None);
+    }
+
+    #[test]
+    fn dependency_information_is_read() {
+        let dir = TempDir::new();
+        let path = dir.write("fixture", elf());
+
+        let info = AuditableInfo::read(&path).unwrap();
+
+        assert_eq!(info.packages.len(), 2);
+        assert_eq!(info.packages[0].name, "fixture");
+        assert_eq!(info.packages[0].dependencies, [1]);
+        assert_eq!(info.packages[1].source, "crates.io");
+        assert_eq!(info.root(), Some(0));
+    }
+
+    #[test]
+    fn truncated_binaries_are_errors() {
+        for bytes in [elf(), pe(), mach_o()] {
+            for len in 0..bytes.len() {
+                assert!(
+                    find_section(&bytes[..len]).is_err(),
+                    "{} bytes of {}",
+                    len,
+                    bytes.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn overflowing_elf_offsets_are_errors() {
+        let mut bytes = elf();
+        put(&mut bytes, 0x28, &(u64::MAX - 8).to_le_bytes());
+        assert!(find_section(&bytes).is_err());
+
+        let mut bytes = elf();
+        put(&mut bytes, 0x3a, &u16::MAX.to_le_bytes());
+        put(&mut bytes, 0x3e, &u16::MAX.to_le_bytes());
+        assert!(find_section(&bytes).is_err());
+
+        let mut bytes = elf();
+        let headers = 64 + compressed().len() + 19;
+        put(&mut bytes, headers + 2 * 64 + 0x18, &u64::MAX.to_le_bytes());
+        assert!(find_section(&bytes).is_err());
+    }
+
+    #[test]
+    fn overflowing_pe_offsets_are_errors() {
+        let mut bytes = pe();
+        put(&mut bytes, 0x3c, &u32::MAX.to_le_bytes());
+        assert!(find_section(&bytes).is_err());
+
+        let mut bytes = pe();
+        put(&mut bytes, 0x40 + 64 + 20, &u32::MAX.to_le_bytes());
+        assert!(find_section(&bytes).is_err());
+    }
+
+    #[test]
+    fn overflowing_mach_o_offsets_are_errors() {
+        let mut bytes = mach_o();
+        put(&mut bytes, 36, &u32::MAX.to_le_bytes());
+        assert!(find_section(&bytes).is_err());
+
+        let mut bytes = mach_o();
+        put(&mut bytes, 48 + 64, &u32::MAX.to_le_bytes());
+        put(&mut bytes, 48 + 72, b".other\0");
+        assert!(find_section(&bytes).is_err());
+    }
+}
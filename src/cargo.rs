@@ -1,27 +1,735 @@
 //! Functions for interacting with `cargo-metadata`.
 
+use crate::GenerateOptions;
 use anyhow::{anyhow, Result};
-use cargo_metadata::{Metadata, MetadataCommand, Package};
+use cargo_metadata::{
+    CargoOpt, DependencyKind, Message, Metadata, MetadataCommand, Node, NodeDep, Package, PackageId,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::fs;
+use std::io::{self, BufReader, Read};
+use std::ops::Not as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
 /// Metadata of the crate being documented.
-pub struct CrateMetadata(
+pub struct CrateMetadata {
     /// The metadata.
-    Metadata,
-);
+    metadata: Metadata,
+    /// The IDs of the packages included in the document.
+    included: HashSet<PackageId>,
+    /// The kinds of dependencies which are included.
+    kinds: IncludedKinds,
+    /// Whether the whole workspace is being documented.
+    workspace: bool,
+    /// The workspace member selected as the root package, if any.
+    selected: Option<PackageId>,
+    /// Whether dependencies deeper than the maximum depth were left out.
+    truncated: bool,
+}
 
 impl CrateMetadata {
     /// Load crate metadata.
-    pub fn load() -> Result<Self> {
+    pub fn load(args: &GenerateOptions) -> Result<Self> {
         log::info!(target: "cargo_spdx", "loading crate metadata");
-        Ok(CrateMetadata(MetadataCommand::new().exec()?))
+
+        let metadata = match args.metadata() {
+            Some(path) => read_metadata(path)?,
+            None => get_metadata_command(args).exec()?,
+        };
+
+        CrateMetadata::from_metadata(args, metadata)
+    }
+
+    /// Load the crate metadata of the workspace with the manifest at `manifest_path`, rather
+    /// than the one in the current directory. Any `--metadata` file is ignored.
+    pub fn load_manifest(args: &GenerateOptions, manifest_path: &Path) -> Result<Self> {
+        log::info!(
+            target: "cargo_spdx",
+            "loading crate metadata for '{}'",
+            manifest_path.display()
+        );
+
+        let metadata = get_metadata_command(args)
+            .manifest_path(manifest_path)
+            .exec()?;
+
+        CrateMetadata::from_metadata(args, metadata)
+    }
+
+    /// Find the packages included in the document from the output of `cargo metadata`.
+    fn from_metadata(args: &GenerateOptions, metadata: Metadata) -> Result<Self> {
+        let kinds = IncludedKinds {
+            dev: args.exclude_dev_dependencies().not(),
+            build: args.include_build_dependencies(),
+        };
+        let selected = args
+            .package()
+            .map(|name| find_member(&metadata, name))
+            .transpose()?;
+
+        // Only the dependencies of the selected member are included, if there is one.
+        let roots = match &selected {
+            Some(id) => vec![id],
+            None => metadata.workspace_members.iter().collect(),
+        };
+        let (mut included, truncated) = find_included(&metadata, roots, kinds, args.max_depth());
+
+        if let Some(bin) = args.prune_to_bin() {
+            let compiled = find_compiled(args, bin)?;
+            included.retain(|id| compiled.contains(id));
+        }
+
+        Ok(CrateMetadata {
+            metadata,
+            included,
+            kinds,
+            workspace: args.workspace(),
+            selected,
+            truncated,
+        })
     }
 
     /// Extract the root package info from the crate metadata.
+    ///
+    /// This is the workspace member selected with `--package`, if there is one. Virtual
+    /// workspaces have no root package otherwise, and the error says how to pick one.
     pub fn root(&self) -> Result<&Package> {
-        self.0
+        if let Some(id) = &self.selected {
+            return Ok(&self.metadata[id]);
+        }
+
+        self.metadata
             .resolve
             .as_ref()
-            .and_then(|r| r.root.as_ref().map(|r| &self.0[r]))
-            .ok_or_else(|| anyhow!("no root found"))
+            .and_then(|r| r.root.as_ref().map(|r| &self.metadata[r]))
+            .ok_or_else(|| {
+                anyhow!(
+                    "no root package; this is a virtual workspace, so use --package to pick a \
+                     member or --workspace to describe every member"
+                )
+            })
+    }
+
+    /// Get the name to use for the document.
+    ///
+    /// This is the name of the root package. When documenting a workspace with a virtual
+    /// manifest there's no root package, so the name of the workspace directory is used.
+    pub fn name(&self) -> Result<&str> {
+        match self.root() {
+            Ok(root) => Ok(&root.name),
+            Err(_) if self.workspace => self
+                .metadata
+                .workspace_root
+                .file_name()
+                .ok_or_else(|| anyhow!("workspace root has no directory name")),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get the packages the document describes.
+    ///
+    /// This is every workspace member when documenting a workspace, or otherwise
+    /// just the root package.
+    pub fn described(&self) -> Result<Vec<&Package>> {
+        if self.workspace {
+            Ok(self
+                .metadata
+                .workspace_members
+                .iter()
+                .map(|id| &self.metadata[id])
+                .collect())
+        } else {
+            Ok(vec![self.root()?])
+        }
+    }
+
+    /// Whether dependencies deeper than the maximum depth were left out of the document.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Iterate over all packages included in the document.
+    pub fn packages(&self) -> impl Iterator<Item = &Package> {
+        self.metadata
+            .packages
+            .iter()
+            .filter(move |package| self.included.contains(&package.id))
+    }
+
+    /// Iterate over the nodes of the resolved dependency graph included in the document.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.metadata
+            .resolve
+            .as_ref()
+            .map(|r| r.nodes.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter(move |node| self.included.contains(&node.id))
+    }
+
+    /// Iterate over the dependencies of a node which are included in the document.
+    pub fn dependencies<'m>(&'m self, node: &'m Node) -> impl Iterator<Item = &'m Package> {
+        node.deps
+            .iter()
+            .filter(move |dep| self.included.contains(&dep.pkg) && self.kinds.follows(dep))
+            .map(move |dep| &self.metadata[&dep.pkg])
+    }
+
+    /// Get the root directory of the workspace.
+    pub fn workspace_root(&self) -> &Path {
+        self.metadata.workspace_root.as_std_path()
+    }
+
+    /// Get Cargo's target directory, which may have been moved by `CARGO_TARGET_DIR` or the
+    /// workspace configuration.
+    pub fn target_directory(&self) -> &Path {
+        self.metadata.target_directory.as_std_path()
+    }
+
+    /// Get the version of Cargo's feature resolver used for the workspace, like "2".
+    ///
+    /// This is the `resolver` field of the workspace's root manifest, or otherwise the default
+    /// for the root package's edition. Returns `None` if the root manifest can't be read.
+    pub fn resolver(&self) -> Option<String> {
+        let manifest_path = self.metadata.workspace_root.join("Cargo.toml");
+
+        let manifest = match read_manifest(manifest_path.as_std_path()) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                log::warn!(
+                    target: "cargo_spdx",
+                    "failed to read the resolver version from '{}': {}",
+                    manifest_path,
+                    e
+                );
+                return None;
+            }
+        };
+
+        let resolver = ["workspace", "package"]
+            .iter()
+            .find_map(|table| manifest.get(table)?.get("resolver")?.as_str());
+
+        // The 2021 edition made version 2 the default.
+        match (resolver, self.root()) {
+            (Some(resolver), _) => Some(resolver.to_owned()),
+            (None, Ok(root)) if root.edition.as_str() >= "2021" => Some(String::from("2")),
+            (None, _) => Some(String::from("1")),
+        }
+    }
+
+    /// Get the link-time optimization and stripping settings of a build profile, like
+    /// `lto=fat`.
+    ///
+    /// Only settings given in the `[profile]` table of the workspace's root manifest are
+    /// returned, so Cargo's defaults are left out.
+    pub fn profile_settings(&self, profile: &str) -> Vec<String> {
+        let manifest_path = self.metadata.workspace_root.join("Cargo.toml");
+
+        let manifest = match read_manifest(manifest_path.as_std_path()) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                log::warn!(
+                    target: "cargo_spdx",
+                    "failed to read the profile settings from '{}': {}",
+                    manifest_path,
+                    e
+                );
+                return vec![];
+            }
+        };
+
+        let table = manifest
+            .get("profile")
+            .and_then(|profiles| profiles.get(profile));
+
+        ["lto", "strip"]
+            .iter()
+            .filter_map(|key| {
+                let value = table?.get(key)?;
+                let value = value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), ToOwned::to_owned);
+                Some(format!("{}={}", key, value))
+            })
+            .collect()
+    }
+
+    /// Look up a package by its ID.
+    pub fn package(&self, id: &PackageId) -> &Package {
+        &self.metadata[id]
+    }
+}
+
+/// The checksums of the registry packages recorded in `Cargo.lock`.
+#[derive(Debug)]
+pub struct LockChecksums(
+    /// The SHA-256 checksum of each package, keyed on its name, version, and source.
+    HashMap<(String, String, String), String>,
+);
+
+impl LockChecksums {
+    /// Read the checksums from the `Cargo.lock` in the workspace root.
+    ///
+    /// Returns `None` with a warning if there's no lockfile or it can't be read.
+    pub fn read(metadata: &CrateMetadata) -> Option<Self> {
+        let path = metadata.workspace_root().join("Cargo.lock");
+
+        let lockfile = match read_manifest(&path) {
+            Ok(lockfile) => lockfile,
+            Err(e) => {
+                log::warn!(
+                    target: "cargo_spdx",
+                    "failed to read '{}', so packages won't have checksums: {}",
+                    path.display(),
+                    e
+                );
+                return None;
+            }
+        };
+
+        let mut checksums = lockfile
+            .get("package")
+            .and_then(|packages| packages.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|package| {
+                let field = |key| package.get(key)?.as_str().map(ToOwned::to_owned);
+
+                Some((
+                    (field("name")?, field("version")?, field("source")?),
+                    field("checksum")?,
+                ))
+            })
+            .collect::<HashMap<_, _>>();
+
+        // Version 1 lockfiles record checksums in a table of their own, rather than with
+        // each package.
+        let v1_checksums = read_v1_checksums(&lockfile);
+
+        if v1_checksums.is_empty().not() {
+            log::warn!(
+                target: "cargo_spdx",
+                "'{}' is in the version 1 lockfile format, which newer versions of Cargo \
+                 rewrite; its checksums are read from its [metadata] table",
+                path.display()
+            );
+            checksums.extend(v1_checksums);
+        }
+
+        Some(LockChecksums(checksums))
+    }
+
+    /// Get the checksum of a package, if one was recorded.
+    pub fn get(&self, package: &Package) -> Option<&str> {
+        let source = package.source.as_ref()?;
+        let key = (
+            package.name.clone(),
+            package.version.to_string(),
+            source.repr.clone(),
+        );
+
+        self.0.get(&key).map(String::as_str)
+    }
+}
+
+/// Read the checksums of a version 1 lockfile, from the `[metadata]` entries of the form
+/// `"checksum <name> <version> (<source>)" = "<checksum>"`.
+fn read_v1_checksums(lockfile: &toml::Value) -> HashMap<(String, String, String), String> {
+    lockfile
+        .get("metadata")
+        .and_then(|metadata| metadata.as_table())
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| {
+            let mut parts = key.strip_prefix("checksum ")?.splitn(3, ' ');
+            let name = parts.next()?;
+            let version = parts.next()?;
+            let source = parts.next()?.strip_prefix('(')?.strip_suffix(')')?;
+
+            // Packages without a checksum are recorded with a placeholder.
+            let checksum = value.as_str().filter(|checksum| *checksum != "<none>")?;
+
+            Some((
+                (name.to_owned(), version.to_owned(), source.to_owned()),
+                checksum.to_owned(),
+            ))
+        })
+        .collect()
+}
+
+/// Read a manifest or lockfile as a TOML document.
+fn read_manifest(path: &Path) -> Result<toml::Value> {
+    let contents = fs::read_to_string(path)?;
+    Ok(toml::from_str(&contents)?)
+}
+
+/// Find the root directory of the workspace being documented, without resolving its
+/// dependencies.
+///
+/// The workspace root is read from the `--metadata` file if one was given, or from `cargo
+/// metadata` otherwise. Metadata read from stdin can only be read once, so the current
+/// directory is used instead, as it is if there's no workspace to be found.
+pub fn find_workspace_root(args: &GenerateOptions) -> Result<PathBuf> {
+    let metadata = match args.metadata() {
+        Some(path) if path == Path::new("-") => return Ok(env::current_dir()?),
+        Some(path) => read_metadata(path),
+        None => MetadataCommand::new().no_deps().exec().map_err(Into::into),
+    };
+
+    match metadata {
+        Ok(metadata) => Ok(metadata.workspace_root.into_std_path_buf()),
+        Err(e) => {
+            log::debug!(
+                target: "cargo_spdx",
+                "can't find the workspace root, so the current directory is used: {}",
+                e
+            );
+            Ok(env::current_dir()?)
+        }
+    }
+}
+
+/// Read pre-generated `cargo metadata` output from a file, or from stdin if the path is '-'.
+fn read_metadata(path: &Path) -> Result<Metadata> {
+    let json = if path == Path::new("-") {
+        let mut json = String::new();
+        io::stdin().read_to_string(&mut json)?;
+        json
+    } else {
+        fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read metadata '{}': {}", path.display(), e))?
+    };
+
+    Ok(MetadataCommand::parse(json)?)
+}
+
+/// Get the `cargo metadata` command to run, resolving the features the user selected.
+fn get_metadata_command(args: &GenerateOptions) -> MetadataCommand {
+    let mut command = MetadataCommand::new();
+    command.other_options(get_other_options(args));
+
+    if args.all_features() {
+        command.features(CargoOpt::AllFeatures);
+    }
+
+    if args.no_default_features() {
+        command.features(CargoOpt::NoDefaultFeatures);
+    }
+
+    if args.features().is_empty().not() {
+        command.features(CargoOpt::SomeFeatures(args.features().to_vec()));
+    }
+
+    command
+}
+
+/// Get the extra options to pass through to `cargo metadata`.
+fn get_other_options(args: &GenerateOptions) -> Vec<String> {
+    let mut options = vec![];
+
+    // Only resolve the dependencies used on the requested platforms.
+    for target in args.targets() {
+        options.push(String::from("--filter-platform"));
+        options.push(target.clone());
+    }
+
+    // Keep cargo from touching the network or the lockfile, for hermetic builds.
+    if args.offline() {
+        options.push(String::from("--offline"));
+    }
+
+    if args.locked() {
+        options.push(String::from("--locked"));
+    }
+
+    if args.frozen() {
+        options.push(String::from("--frozen"));
+    }
+
+    options
+}
+
+/// Find the ID of the workspace member with the given name.
+fn find_member(metadata: &Metadata, name: &str) -> Result<PackageId> {
+    metadata
+        .workspace_members
+        .iter()
+        .find(|id| metadata[id].name == name)
+        .cloned()
+        .ok_or_else(|| anyhow!("package '{}' isn't a member of the workspace", name))
+}
+
+/// Find the IDs of the packages reachable from the `roots`, along with whether any were left
+/// out for being more than `max_depth` edges away from the nearest root.
+///
+/// A package that is a development or build dependency of one workspace member but a normal
+/// dependency of another is reachable through the normal edge, so it's always kept.
+fn find_included(
+    metadata: &Metadata,
+    roots: Vec<&PackageId>,
+    kinds: IncludedKinds,
+    max_depth: Option<usize>,
+) -> (HashSet<PackageId>, bool) {
+    let nodes: HashMap<&PackageId, &Node> = metadata
+        .resolve
+        .as_ref()
+        .map(|r| r.nodes.iter().map(|node| (&node.id, node)).collect())
+        .unwrap_or_else(HashMap::new);
+
+    let mut included = HashSet::new();
+    let mut omitted = HashSet::new();
+
+    // Packages are visited breadth-first, so each is first reached by its shortest path.
+    let mut queue = roots.into_iter().map(|id| (id, 0)).collect::<VecDeque<_>>();
+
+    while let Some((id, depth)) = queue.pop_front() {
+        if included.insert(id.clone()).not() {
+            continue;
+        }
+
+        if let Some(node) = nodes.get(id) {
+            let deps = node
+                .deps
+                .iter()
+                .filter(|dep| kinds.follows(dep))
+                .map(|dep| &dep.pkg);
+
+            if max_depth.map_or(false, |max| depth >= max) {
+                omitted.extend(deps);
+            } else {
+                queue.extend(deps.map(|dep| (dep, depth + 1)));
+            }
+        }
+    }
+
+    let truncated = omitted.into_iter().any(|id| included.contains(id).not());
+    (included, truncated)
+}
+
+/// Find the IDs of the packages compiled when building the binary target named `bin`.
+///
+/// This runs `cargo build` with the same features, platforms, and profile as the SBOM, and
+/// collects the packages of the artifacts it reports.
+fn find_compiled(args: &GenerateOptions, bin: &str) -> Result<HashSet<PackageId>> {
+    log::info!(target: "cargo_spdx", "building binary '{}' to find its packages", bin);
+
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut command = Command::new(cargo);
+    command.args(["build", "--message-format=json", "--bin", bin]);
+
+    if let Some(package) = args.package() {
+        command.args(["--package", package]);
+    }
+
+    if let Some(profile) = args.profile() {
+        command.args(["--profile", profile]);
+    }
+
+    if args.all_features() {
+        command.arg("--all-features");
+    }
+
+    if args.no_default_features() {
+        command.arg("--no-default-features");
+    }
+
+    if args.features().is_empty().not() {
+        command.args(["--features", &args.features().join(",")]);
+    }
+
+    for option in get_other_options(args) {
+        // `cargo build` takes the platforms to build for with `--target`.
+        if option == "--filter-platform" {
+            command.arg("--target");
+        } else {
+            command.arg(option);
+        }
+    }
+
+    let mut child = command.stdout(Stdio::piped()).spawn()?;
+
+    // PANIC SAFETY: The child's stdout was piped above.
+    let stdout = child.stdout.take().unwrap();
+
+    let mut compiled = HashSet::new();
+
+    for message in Message::parse_stream(BufReader::new(stdout)) {
+        if let Message::CompilerArtifact(artifact) = message? {
+            compiled.insert(artifact.package_id);
+        }
+    }
+
+    let status = child.wait()?;
+
+    if status.success().not() {
+        return Err(anyhow!("failed to build binary '{}': {}", bin, status));
+    }
+
+    Ok(compiled)
+}
+
+/// The kinds of dependencies included in the document, besides normal dependencies.
+#[derive(Debug, Clone, Copy)]
+struct IncludedKinds {
+    /// Whether development dependencies are included.
+    dev: bool,
+    /// Whether build dependencies are included.
+    build: bool,
+}
+
+impl IncludedKinds {
+    /// Check whether a dependency edge should be followed.
+    ///
+    /// Edges are followed if any of the kinds of dependency they're for is included. A
+    /// crate which is both a build dependency and a normal dependency of the same package
+    /// is always followed, through its normal dependency.
+    fn follows(&self, dep: &NodeDep) -> bool {
+        // Versions of Cargo prior to 1.41 don't report dependency kinds at all.
+        dep.dep_kinds.is_empty()
+            || dep.dep_kinds.iter().any(|info| match info.kind {
+                DependencyKind::Development => self.dev,
+                DependencyKind::Build => self.build,
+                _ => true,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{options, MetadataFixture, TempDir};
+
+    /// Get the names of the dependencies of a crate with an optional dependency, resolved
+    /// with the given options.
+    fn resolved_dependencies(args: &[&str]) -> Vec<String> {
+        let dir = TempDir::new();
+        let manifest = dir.write(
+            "fixture/Cargo.toml",
+            "[package]\n\
+             name = \"fixture\"\n\
+             version = \"0.1.0\"\n\
+             edition = \"2021\"\n\
+             \n\
+             [dependencies]\n\
+             optional-dep = { path = \"../optional-dep\", optional = true }\n\
+             \n\
+             [features]\n\
+             extra = [\"optional-dep\"]\n",
+        );
+        dir.write("fixture/src/lib.rs", "");
+        dir.write(
+            "optional-dep/Cargo.toml",
+            "[package]\nname = \"optional-dep\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        );
+        dir.write("optional-dep/src/lib.rs", "");
+
+        let mut all_args = vec!["--offline"];
+        all_args.extend_from_slice(args);
+
+        let metadata = get_metadata_command(&options(&all_args))
+            .manifest_path(manifest)
+            .exec()
+            .unwrap();
+
+        // PANIC SAFETY: The fixture is a single package, which is the root of the resolve.
+        let resolve = metadata.resolve.unwrap();
+        let root = resolve.root.unwrap();
+        let node = resolve.nodes.iter().find(|node| node.id == root).unwrap();
+
+        node.deps.iter().map(|dep| dep.name.clone()).collect()
+    }
+
+    #[test]
+    fn optional_dependencies_are_left_out_by_default() {
+        assert!(resolved_dependencies(&[]).is_empty());
+    }
+
+    #[test]
+    fn optional_dependencies_follow_features() {
+        assert_eq!(
+            resolved_dependencies(&["--features", "extra"]),
+            ["optional_dep"]
+        );
+        assert_eq!(resolved_dependencies(&["--all-features"]), ["optional_dep"]);
+    }
+
+    #[test]
+    fn workspace_root_is_read_from_the_metadata_file() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        fixture.member(&root);
+
+        let (options, _) = fixture.load(&[]);
+        assert_eq!(find_workspace_root(&options).unwrap(), dir.path());
+    }
+
+    #[test]
+    fn virtual_workspaces_say_how_to_pick_a_package() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let first = fixture.package("first", "0.1.0");
+        let second = fixture.package("second", "0.1.0");
+        fixture.member(&first);
+        fixture.member(&second);
+
+        // Virtual manifests have no root package.
+        let mut json = fixture.to_json();
+        json["resolve"]["root"] = serde_json::Value::Null;
+        let path = dir.write("metadata.json", json.to_string());
+
+        let metadata =
+            CrateMetadata::load(&options(&["--metadata", path.to_str().unwrap()])).unwrap();
+        let error = metadata.root().unwrap_err().to_string();
+
+        assert!(error.contains("virtual workspace"), "{}", error);
+        assert!(error.contains("--package"), "{}", error);
+        assert!(error.contains("--workspace"), "{}", error);
+    }
+
+    #[test]
+    fn version_1_lockfile_checksums_are_read() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let source = "registry+https://github.com/rust-lang/crates.io-index";
+        let dependency = fixture.package_with("dependency", "1.0.0", |json| {
+            json["id"] = serde_json::json!(format!("dependency 1.0.0 ({})", source));
+            json["source"] = serde_json::json!(source);
+        });
+        fixture.member(&root);
+        fixture.depend(&root, &dependency);
+
+        dir.write(
+            "Cargo.lock",
+            format!(
+                "[[package]]\n\
+                 name = \"dependency\"\n\
+                 version = \"1.0.0\"\n\
+                 source = \"{source}\"\n\
+                 \n\
+                 [[package]]\n\
+                 name = \"fixture\"\n\
+                 version = \"0.1.0\"\n\
+                 \n\
+                 [metadata]\n\
+                 \"checksum dependency 1.0.0 ({source})\" = \"{checksum}\"\n",
+                source = source,
+                checksum = "ab".repeat(32),
+            ),
+        );
+
+        let (_, metadata) = fixture.load(&[]);
+        let checksums = LockChecksums::read(&metadata).unwrap();
+        let package = metadata
+            .packages()
+            .find(|package| package.name == "dependency")
+            .unwrap();
+
+        assert_eq!(checksums.get(package), Some("ab".repeat(32).as_str()));
     }
 }
@@ -1,36 +1,85 @@
 //! Defines the CLI for `cargo-spdx`.
 
-use crate::format::Format;
+use crate::cargo::find_workspace_root;
+use crate::config::Config;
+use crate::document::{
+    Algorithm, ExternalDocumentReference, FileType, FileTypeOverrides, RelationshipType,
+    SpdxVersion,
+};
+use crate::format::{Format, JsonStyle};
+use crate::output::Compression;
 use anyhow::{anyhow, Result};
-use clap::Parser;
+use clap::{ArgEnum, Parser, Subcommand};
 use dialoguer::Input;
+use log::LevelFilter;
 use std::borrow::Cow;
 use std::ffi::OsStr;
-use std::ops::Deref;
+use std::num::NonZeroUsize;
 use std::ops::Not as _;
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::thread;
+use time::macros::format_description;
+use time::OffsetDateTime;
+use url::Url;
 
 #[allow(missing_docs)]
-#[derive(Parser)]
+#[derive(Debug, Parser)]
 #[clap(bin_name = "cargo")]
 pub enum Args {
     /// Generate an SPDX SBOM for a crate.
-    Spdx(SpdxArgs),
+    Spdx(GenerateOptions),
 }
 
 impl Args {
     /// Read arguments from the CLI.
     pub fn read() -> Result<Self> {
         log::info!(target: "cargo_spdx", "parsing cli arguments");
-        Ok(Args::try_parse()?)
+        let mut args = Args::try_parse()?;
+
+        // An explicit format must agree with the one implied by the output file extension.
+        if let (Some(format), Some(inferred)) = (args.format, args.inferred_format()) {
+            if format != inferred {
+                return Err(anyhow!(
+                    "--format is {} but the output file extension implies {}",
+                    format,
+                    inferred
+                ));
+            }
+        }
+
+        // Settings from configuration files fill in whatever wasn't given on the CLI. The
+        // workspace's file is found from its root, wherever in the workspace we're run.
+        let workspace_root = find_workspace_root(&args)?;
+        args.apply_config(Config::load(&workspace_root)?)?;
+
+        // Writing the document out twice in one format would only overwrite it.
+        check_also_formats(&args)?;
+
+        // Post-processing scripts are given the JSON of the document, so they can only
+        // change output in formats written from it.
+        if args.post_process_script().is_some() {
+            let formats = std::iter::once(args.format()).chain(args.also_formats().iter().copied());
+
+            for format in formats {
+                if matches!(format, Format::Json | Format::Yaml).not() {
+                    return Err(anyhow!(
+                        "--post-process-script only applies to JSON and YAML output, not {}",
+                        format
+                    ));
+                }
+            }
+        }
+
+        Ok(args)
     }
 }
 
 // Use a Deref impl to avoid the rest of the codebase having to care
 // about the nesting structure required here.
 impl Deref for Args {
-    type Target = SpdxArgs;
+    type Target = GenerateOptions;
 
     fn deref(&self) -> &Self::Target {
         match self {
@@ -39,31 +88,406 @@ impl Deref for Args {
     }
 }
 
-/// The inner argument type.
-#[derive(Parser)]
+impl DerefMut for Args {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Args::Spdx(inner) => inner,
+        }
+    }
+}
+
+/// Options for generating an SBOM.
+///
+/// These are the options of the `cargo spdx` command. Outside the CLI they can be
+/// parsed from a list of arguments with `GenerateOptions::try_parse_from`.
+#[derive(Debug, Parser)]
 #[clap(version, about, long_about = None)]
-pub struct SpdxArgs {
-    /// The output format to use: 'kv' (default), 'json', 'yaml', 'rdf'.
+pub struct GenerateOptions {
+    /// The output format to use: 'kv' (default), 'json', 'yaml', 'rdf', 'spdx3', 'ndjson'.
+    ///
+    /// 'ndjson' writes one record per line for internal pipelines, and isn't an SPDX format.
+    ///
+    /// If not given, the format is inferred from the extension of the output file.
     #[clap(short, long)]
     #[clap(parse(try_from_str = parse_format))]
     format: Option<Format>,
 
+    /// Also write the document out in this format, to the same path with the extension of
+    /// the format. May be repeated.
+    #[clap(long = "also-format", value_name = "FORMAT")]
+    #[clap(parse(try_from_str = parse_format))]
+    also_formats: Vec<Format>,
+
+    /// How JSON output is laid out: 'pretty' (default), 'compact'. Keys are always sorted.
+    #[clap(long, arg_enum, default_value = "pretty")]
+    json_style: JsonStyle,
+
+    /// Begin YAML output with a '---' document start marker.
+    #[clap(long)]
+    yaml_document_start: bool,
+
+    /// The version of the SPDX specification to write: '2.2' (default), '2.3'.
+    #[clap(long, default_value = "2.2")]
+    spdx_version: SpdxVersion,
+
     /// The URL where the SBOM will be hosted. Must be unique for each SBOM.
+    ///
+    /// If not given, the namespace is derived from the git origin remote and current commit.
     #[clap(short = 'H', long)]
     host_url: Option<String>,
 
-    /// The path of the desired output file.
+    /// The namespace of the document, used as-is instead of deriving one from the host URL.
+    #[clap(long, conflicts_with = "host_url")]
+    namespace: Option<String>,
+
+    /// Reference another SPDX document, as '<namespace>=<id>=<sha1>'. May be repeated.
+    #[clap(long = "external-document-ref", value_name = "NAMESPACE=ID=SHA1")]
+    external_document_references: Vec<ExternalDocumentReference>,
+
+    /// The person creating the SBOM, as 'Name <email>'. Replaces the person detected from Git.
+    #[clap(long)]
+    creator: Option<String>,
+
+    /// A freeform comment about the creation of the SBOM.
+    #[clap(long)]
+    creator_comment: Option<String>,
+
+    /// A freeform comment about the SBOM.
+    #[clap(long)]
+    document_comment: Option<String>,
+
+    /// The path of the desired output file, or '-' to write to stdout.
     #[clap(short, long)]
     #[clap(parse(try_from_os_str = parse_output))]
     output: Option<PathBuf>,
 
-    /// Force the output, replacing any existing file with the same name.
+    /// The directory to write the output file into, keeping its default name. It's created
+    /// if it doesn't exist.
+    #[clap(long, conflicts_with = "output")]
+    output_directory: Option<PathBuf>,
+
+    /// Write a tar archive to this path instead of a single document, holding a smaller
+    /// document for each package, describing it along with its direct dependencies. Each is
+    /// named after the SPDX identifier of its package.
+    #[clap(long, value_name = "PATH")]
+    #[clap(conflicts_with_all = &[
+        "output",
+        "output_directory",
+        "also_formats",
+        "post_process_script",
+    ])]
+    archive: Option<PathBuf>,
+
+    /// Compress the output, appending the extension of the compression scheme: 'gzip'.
+    /// Implied by an output path ending in '.gz'.
+    #[clap(long, arg_enum)]
+    compress: Option<Compression>,
+
+    /// Force the output, replacing any existing file with the same name and creating any
+    /// missing parent directories.
     #[clap(short = 'F', long)]
     force: bool,
 
+    /// Insert the time of the run into the output file name, like
+    /// 'crate-20240101T120000.spdx', so successive runs don't replace each other's output.
+    ///
+    /// This applies to an output path given with '--output' too.
+    #[clap(long)]
+    timestamp_suffix: bool,
+
+    /// When the run started, for naming output files.
+    #[clap(skip = OffsetDateTime::now_utc())]
+    started: OffsetDateTime,
+
+    /// Only log errors, overriding RUST_LOG.
+    #[clap(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log progress, overriding RUST_LOG. Repeat for more detail.
+    #[clap(short, long, parse(from_occurrences))]
+    verbose: u64,
+
+    /// Print a summary of the output instead of writing it.
+    #[clap(long)]
+    dry_run: bool,
+
     /// Do not run interactively.
     #[clap(short = 'n', long = "no-interact")]
     no_interact: bool,
+
+    /// Exclude packages only reachable through development dependencies.
+    #[clap(long)]
+    exclude_dev_dependencies: bool,
+
+    /// Include packages reachable through build dependencies, like proc-macros and code
+    /// generators, which run at build time but aren't shipped: 'true' (default), 'false'.
+    ///
+    /// A crate which is also a normal dependency is always included.
+    #[clap(long, value_name = "BOOL", default_value = "true", parse(try_from_str))]
+    include_build_dependencies: bool,
+
+    /// Only include dependencies used on the given target triple. May be repeated.
+    #[clap(long = "target", value_name = "TRIPLE")]
+    targets: Vec<String>,
+
+    /// Read the output of `cargo metadata` from a JSON file, or '-' for stdin, instead of
+    /// running cargo.
+    #[clap(long, value_name = "PATH")]
+    #[clap(conflicts_with_all = &[
+        "targets",
+        "offline",
+        "locked",
+        "frozen",
+        "features",
+        "all_features",
+        "no_default_features",
+    ])]
+    metadata: Option<PathBuf>,
+
+    /// Features to activate when resolving dependencies. May be repeated or comma-separated.
+    ///
+    /// Like the other feature flags, this changes which optional dependencies, and so which
+    /// packages, appear in the SBOM.
+    #[clap(long, value_name = "FEATURES", use_value_delimiter = true)]
+    features: Vec<String>,
+
+    /// Activate all features when resolving dependencies.
+    #[clap(long)]
+    all_features: bool,
+
+    /// Don't activate the default features when resolving dependencies.
+    #[clap(long)]
+    no_default_features: bool,
+
+    /// Run `cargo metadata` without accessing the network.
+    #[clap(long)]
+    offline: bool,
+
+    /// Require `Cargo.lock` to be up to date when running `cargo metadata`.
+    #[clap(long)]
+    locked: bool,
+
+    /// Require `Cargo.lock` to be up to date and run `cargo metadata` without accessing the network.
+    #[clap(long)]
+    frozen: bool,
+
+    /// The build profile the SBOM corresponds to, like 'release', recorded in the comment on
+    /// the described packages along with its LTO and strip settings.
+    #[clap(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Describe the workspace member with the given name and its dependencies, rather than
+    /// the root package.
+    #[clap(short, long, value_name = "NAME", conflicts_with = "workspace")]
+    package: Option<String>,
+
+    /// Describe every member of the workspace in one document, rather than just the root package.
+    ///
+    /// This also works for virtual manifests, where the document is named after the
+    /// workspace directory.
+    #[clap(long)]
+    workspace: bool,
+
+    /// Only include the packages actually compiled into the binary target with the given name.
+    ///
+    /// Packages which are resolved but never compiled, like those only used by code behind
+    /// inactive features, are dropped. This runs `cargo build` for the binary, so it's much
+    /// slower than generating the SBOM from the crate metadata alone.
+    #[clap(long, value_name = "BIN", conflicts_with_all = &["workspace", "metadata"])]
+    prune_to_bin: Option<String>,
+
+    /// Only include dependencies within this many edges of the root package, so 0 includes
+    /// just the root package and 1 its direct dependencies. The document is annotated when
+    /// dependencies are left out.
+    #[clap(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Produce byte-identical output for identical inputs.
+    ///
+    /// The creation time is the Unix epoch unless SOURCE_DATE_EPOCH is set, which is honored
+    /// with or without this flag, and packages and relationships are sorted by their
+    /// identifiers.
+    #[clap(long)]
+    reproducible: bool,
+
+    /// Analyze the files of the described packages, recording their checksums.
+    #[clap(long)]
+    with_files: bool,
+
+    /// The maximum number of files to analyze in each package. Analyzing a package with more
+    /// files fails, rather than leaving some out of its verification code.
+    #[clap(long, default_value_t = 10_000)]
+    max_files: usize,
+
+    /// The number of threads used to analyze files. Defaults to the number of CPUs.
+    #[clap(short, long)]
+    jobs: Option<usize>,
+
+    /// The algorithms to checksum analyzed files with: 'sha1', 'sha256' (default: both),
+    /// 'sha512', 'md5'.
+    ///
+    /// SHA-1 is always included, since SPDX requires it. MD5 is never included by default.
+    #[clap(long, arg_enum, use_value_delimiter = true)]
+    checksum_algorithms: Vec<Algorithm>,
+
+    /// The types of relationships to include: 'describes', 'depends_on', 'contains',
+    /// 'build_dependency_of' (default: all of them).
+    ///
+    /// Packages only contain files with --with-files, and only proc-macros are build
+    /// dependencies.
+    #[clap(long, arg_enum, use_value_delimiter = true)]
+    relationships: Vec<RelationshipType>,
+
+    /// Leave out the relationship saying the document describes the root package, for
+    /// documents embedded in a larger one which describes it instead. Relationships between
+    /// packages are kept.
+    #[clap(long)]
+    no_root_describes: bool,
+
+    /// The directory analyzed files are named relative to, instead of the workspace root.
+    ///
+    /// Files outside of it are named by their absolute paths, with a warning.
+    #[clap(long, value_name = "PATH")]
+    strip_prefix: Option<PathBuf>,
+
+    /// Give analyzed files with an extension a type, like 'md=TEXT', instead of the default
+    /// types for it. May be repeated to give files several types.
+    ///
+    /// The types are 'SOURCE', 'BINARY', 'TEXT', 'DOCUMENTATION', and 'OTHER'.
+    #[clap(long, value_name = "EXT=TYPE", parse(try_from_str = parse_file_type_override))]
+    file_type_override: Vec<(String, FileType)>,
+
+    /// Record the contributors to each analyzed file, from the authors of the commits which
+    /// touched it, or the package's AUTHORS file if there's no git history.
+    #[clap(long, requires = "with_files")]
+    file_contributors: bool,
+
+    /// Annotate packages whose version was yanked from crates.io, for review.
+    #[clap(long)]
+    check_yanked: bool,
+
+    /// Annotate the document with the SHA-256 of the workspace's `Cargo.lock`, so SBOMs
+    /// generated from the same dependency lock can be recognized at a glance.
+    #[clap(long)]
+    annotate_lock_hash: bool,
+
+    /// Conclude that each package has the license it declares, rather than making no assertion.
+    #[clap(long)]
+    conclude_declared: bool,
+
+    /// Rewrite declared licenses with official SPDX identifiers, reading '/' as 'OR'. The
+    /// original expression is recorded in the license comments if it changed.
+    #[clap(long)]
+    normalize_licenses: bool,
+
+    /// A previously generated key-value document to carry concluded licenses, copyright text,
+    /// and license comments forward from, for packages which are still present. License
+    /// comments starting 'Generated by cargo-spdx:' are written afresh rather than carried
+    /// forward, so remove that prefix from any you edit.
+    #[clap(long, value_name = "PATH")]
+    merge_existing: Option<PathBuf>,
+
+    /// Fail if any package has an unknown license.
+    #[clap(long, conflicts_with = "max_unknown_licenses")]
+    fail_on_unknown_license: bool,
+
+    /// Fail if more than this many packages have an unknown license.
+    #[clap(long, value_name = "N")]
+    max_unknown_licenses: Option<usize>,
+
+    /// Check the document against the SPDX 2.3 JSON schema before writing it out, failing
+    /// with the fields which violate it.
+    #[clap(long)]
+    validate: bool,
+
+    /// Only permit packages under this license, given as an SPDX identifier or expression.
+    /// May be repeated.
+    #[clap(long = "allow-license", value_name = "LICENSE")]
+    allowed_licenses: Vec<String>,
+
+    /// Forbid packages under this license, given as an SPDX identifier or expression. May be
+    /// repeated.
+    #[clap(long = "deny-license", value_name = "LICENSE")]
+    denied_licenses: Vec<String>,
+
+    /// Record the version of Cargo's feature resolver in the document comment, since it
+    /// changes which packages appear.
+    #[clap(long)]
+    comment_with_resolver: bool,
+
+    /// Record the command line which generated the document in the document comment.
+    ///
+    /// The values of options and NAME=value pairs whose names contain 'token', 'password',
+    /// 'secret', or 'key' are redacted.
+    #[clap(long)]
+    record_invocation: bool,
+
+    /// Also redact the values of options and NAME=value pairs whose names contain this word
+    /// when recording the command line. May be repeated.
+    #[clap(long = "redact", value_name = "WORD")]
+    redacted_words: Vec<String>,
+
+    /// The type of the package URLs of packages, for registries which mirror crates under a
+    /// type other than 'cargo'.
+    #[clap(long, value_name = "TYPE", default_value = "cargo", parse(try_from_str = parse_purl_type))]
+    purl_type: String,
+
+    /// The namespace of the package URLs of packages, like 'internal' for
+    /// 'pkg:cargo/internal/name@version'.
+    #[clap(long, value_name = "NAMESPACE")]
+    purl_namespace: Option<String>,
+
+    /// Record local path dependencies which are on crates.io at the same version as the
+    /// crates.io packages they're published as.
+    #[clap(long)]
+    strip_path_dependencies: bool,
+
+    /// Reference RustSec advisories affecting the packages in the SBOM.
+    #[clap(long)]
+    with_advisories: bool,
+
+    /// Pass the document through this program before writing it out, to adjust it.
+    ///
+    /// The program is given the document as JSON on stdin, and must print the modified JSON
+    /// on stdout and exit successfully, or nothing is written. It's run once, whichever
+    /// formats are written, and not at all in dry runs. Only JSON and YAML output can be
+    /// post-processed, and archives can't be.
+    #[clap(long, value_name = "PROGRAM")]
+    post_process_script: Option<PathBuf>,
+
+    /// Do something other than generating an SBOM.
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+/// Commands which can be run instead of generating an SBOM.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Check an existing SBOM still matches the packages of the crate.
+    Verify {
+        /// The SBOM to check, in key-value or JSON format.
+        #[clap(value_name = "PATH")]
+        path: PathBuf,
+    },
+    /// Report the packages added, removed, or changed version, and the dependency
+    /// relationships added or removed, between two git refs.
+    ///
+    /// Without a second ref, the working tree is compared, uncommitted changes included.
+    Diff {
+        /// The git ref to compare from.
+        #[clap(value_name = "FROM")]
+        from: String,
+        /// The git ref to compare to, or the working tree if missing.
+        #[clap(value_name = "TO")]
+        to: Option<String>,
+    },
+    /// Generate an SBOM for a prebuilt binary, from the dependency information embedded in it
+    /// by `cargo auditable`, rather than from the crate's source.
+    SbomForBinary {
+        /// The ELF, PE, or Mach-O binary.
+        #[clap(value_name = "PATH")]
+        path: PathBuf,
+    },
 }
 
 /// Parse the format from the CLI input.
@@ -71,34 +495,165 @@ fn parse_format(input: &str) -> Result<Format> {
     let format = Format::from_str(input)?;
 
     match format {
-        Format::KeyValue => Ok(format),
-        Format::Json => return Err(anyhow!("JSON format not implemented")),
-        Format::Yaml => return Err(anyhow!("YAML format not implemented")),
+        Format::KeyValue | Format::Json | Format::Yaml | Format::Spdx3 | Format::Ndjson => {
+            Ok(format)
+        }
         Format::Rdf => return Err(anyhow!("RDF format not implemented")),
     }
 }
 
+/// Parse a package URL type, which is made of ASCII letters, digits, '.', '+', and '-', and
+/// doesn't start with a digit.
+fn parse_purl_type(input: &str) -> Result<String> {
+    let valid = input
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-'))
+        && input.starts_with(|c: char| c.is_ascii_digit().not());
+
+    if valid {
+        Ok(input.to_ascii_lowercase())
+    } else {
+        Err(anyhow!("'{}' isn't a valid package URL type", input))
+    }
+}
+
+/// Parse an override of the type of files with an extension, like 'md=TEXT'.
+fn parse_file_type_override(input: &str) -> Result<(String, FileType)> {
+    let (extension, file_type) = input
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected EXT=TYPE, but got '{}'", input))?;
+    let file_type = FileType::from_str(file_type, true).map_err(|e| anyhow!(e))?;
+
+    Ok((
+        extension.trim_start_matches('.').to_ascii_lowercase(),
+        file_type,
+    ))
+}
+
+/// Check the host URL is an absolute URL, with both a scheme and a host.
+fn validate_host_url(host_url: &str) -> Result<()> {
+    match Url::parse(host_url) {
+        Ok(url) if url.has_host() => Ok(()),
+        _ => Err(anyhow!(
+            "--host-url must be an absolute URL, e.g. https://example.com, but got '{}'",
+            host_url
+        )),
+    }
+}
+
+/// Check each format the document is also written out in is different from the others, and
+/// from the output format.
+fn check_also_formats(args: &GenerateOptions) -> Result<()> {
+    let mut formats = vec![args.format()];
+
+    for format in args.also_formats() {
+        if formats.contains(format) {
+            return Err(anyhow!(
+                "--also-format {} is already a format the document is written out in",
+                format
+            ));
+        }
+
+        formats.push(*format);
+    }
+
+    Ok(())
+}
+
 /// Get a `PathBuf` to a file.
 fn parse_output(input: &OsStr) -> Result<PathBuf> {
     Ok(PathBuf::from(input))
 }
 
-impl Args {
-    /// Get the format selected by the user.
+impl GenerateOptions {
+    /// Get the format selected by the user, or implied by the output file extension.
     #[inline]
     pub fn format(&self) -> Format {
-        self.format.unwrap_or_default()
+        self.format
+            .or_else(|| self.inferred_format())
+            .unwrap_or_default()
+    }
+
+    /// Get the format implied by the output file extension, if any.
+    #[inline]
+    fn inferred_format(&self) -> Option<Format> {
+        let output = self.output()?;
+
+        // A compression extension comes after the extension of the format, so it's skipped.
+        let uncompressed = Compression::value_variants()
+            .iter()
+            .find_map(|compression| {
+                output
+                    .to_str()?
+                    .strip_suffix(compression.extension())
+                    .map(Path::new)
+            })
+            .unwrap_or(output);
+
+        Format::from_path(uncompressed)
+    }
+
+    /// Get the compression implied by the output file extension, if any.
+    #[inline]
+    fn inferred_compression(&self) -> Option<Compression> {
+        let output = self.output()?.to_str()?;
+
+        Compression::value_variants()
+            .iter()
+            .copied()
+            .find(|compression| output.ends_with(compression.extension()))
+    }
+
+    /// Get the other formats to write the document out in as well.
+    #[inline]
+    pub fn also_formats(&self) -> &[Format] {
+        &self.also_formats
+    }
+
+    /// Get how JSON output is laid out.
+    #[inline]
+    pub fn json_style(&self) -> JsonStyle {
+        self.json_style
+    }
+
+    /// Whether YAML output begins with a document start marker.
+    #[inline]
+    pub fn yaml_document_start(&self) -> bool {
+        self.yaml_document_start
+    }
+
+    /// Get the version of the SPDX specification to write.
+    #[inline]
+    pub fn spdx_version(&self) -> SpdxVersion {
+        self.spdx_version
     }
 
     /// Get the URL the SBOM will be hosted.
+    ///
+    /// Returns an error if it isn't an absolute URL.
     #[inline]
     pub fn host_url(&self) -> Result<Cow<'_, str>> {
+        let host_url = self.raw_host_url()?;
+        validate_host_url(&host_url)?;
+        Ok(host_url)
+    }
+
+    /// Whether a host URL was given, on the CLI or in a configuration file.
+    #[inline]
+    pub fn has_host_url(&self) -> bool {
+        self.host_url.is_some()
+    }
+
+    /// Get the URL the SBOM will be hosted, without validating it.
+    #[inline]
+    fn raw_host_url(&self) -> Result<Cow<'_, str>> {
         match &self.host_url {
             Some(host_url) => Ok(Cow::Borrowed(host_url)),
             None => {
                 if self.is_interactive().not() {
                     return Err(anyhow!(
-                        "if running non-interactively, --host-url must be specified"
+                        "if running non-interactively outside a git repository with an origin \
+                         remote, --host-url must be specified"
                     ));
                 }
 
@@ -111,12 +666,67 @@ impl Args {
         }
     }
 
+    /// Get the namespace pinned by the user, if any.
+    #[inline]
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Get the other SPDX documents to reference.
+    #[inline]
+    pub fn external_document_references(&self) -> &[ExternalDocumentReference] {
+        &self.external_document_references
+    }
+
+    /// Get the creator given by the user, if any.
+    #[inline]
+    pub fn creator(&self) -> Option<&str> {
+        self.creator.as_deref()
+    }
+
+    /// Get the comment about the creation of the SBOM, if any.
+    #[inline]
+    pub fn creator_comment(&self) -> Option<&str> {
+        self.creator_comment.as_deref()
+    }
+
+    /// Get the comment about the SBOM, if any.
+    #[inline]
+    pub fn document_comment(&self) -> Option<&str> {
+        self.document_comment.as_deref()
+    }
+
     /// Get the possible output path of the program.
     #[inline]
     pub fn output(&self) -> Option<&Path> {
         self.output.as_deref()
     }
 
+    /// Get the directory the output file is written into, if any.
+    #[inline]
+    pub fn output_directory(&self) -> Option<&Path> {
+        self.output_directory.as_deref()
+    }
+
+    /// Get the path of the archive of per-package documents to write, if any.
+    #[inline]
+    pub fn archive(&self) -> Option<&Path> {
+        self.archive.as_deref()
+    }
+
+    /// Get the compression selected by the user, or implied by the output file extension,
+    /// if any.
+    #[inline]
+    pub fn compression(&self) -> Option<Compression> {
+        self.compress.or_else(|| self.inferred_compression())
+    }
+
+    /// Whether to only summarize the output, rather than writing it.
+    #[inline]
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// Whether we should forcefully overwrite prior output.
     #[inline]
     pub fn force(&self) -> bool {
@@ -129,9 +739,462 @@ impl Args {
         self.format().extension()
     }
 
+    /// Get the timestamp to insert into the output file name, if one should be.
+    #[inline]
+    pub fn timestamp_suffix(&self) -> Option<String> {
+        let format = format_description!("[year][month][day]T[hour][minute][second]");
+
+        self.timestamp_suffix
+            // PANIC SAFETY: The format only has components every date and time has.
+            .then(|| self.started.format(format).unwrap())
+    }
+
+    /// Whether packages only needed as development dependencies are excluded.
+    #[inline]
+    pub fn exclude_dev_dependencies(&self) -> bool {
+        self.exclude_dev_dependencies
+    }
+
+    /// Whether packages only needed as build dependencies are included.
+    #[inline]
+    pub fn include_build_dependencies(&self) -> bool {
+        self.include_build_dependencies
+    }
+
+    /// Get the target triples to resolve dependencies for.
+    #[inline]
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    /// Get the path of pre-generated `cargo metadata` output, if any.
+    #[inline]
+    pub fn metadata(&self) -> Option<&Path> {
+        self.metadata.as_deref()
+    }
+
+    /// Get the features to activate when resolving dependencies.
+    #[inline]
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    /// Whether all features are activated when resolving dependencies.
+    #[inline]
+    pub fn all_features(&self) -> bool {
+        self.all_features
+    }
+
+    /// Whether the default features aren't activated when resolving dependencies.
+    #[inline]
+    pub fn no_default_features(&self) -> bool {
+        self.no_default_features
+    }
+
+    /// Whether `cargo metadata` runs without accessing the network.
+    #[inline]
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Whether `cargo metadata` requires `Cargo.lock` to be up to date.
+    #[inline]
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Whether `cargo metadata` requires `Cargo.lock` to be up to date and runs without
+    /// accessing the network.
+    #[inline]
+    pub fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Get the binary target whose compiled packages the SBOM is pruned to, if one was given.
+    #[inline]
+    pub fn prune_to_bin(&self) -> Option<&str> {
+        self.prune_to_bin.as_deref()
+    }
+
+    /// Get the maximum depth of dependencies to include, if one was given.
+    #[inline]
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Get the build profile the SBOM corresponds to, if one was given.
+    #[inline]
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Get the name of the workspace member to describe, if one was selected.
+    #[inline]
+    pub fn package(&self) -> Option<&str> {
+        self.package.as_deref()
+    }
+
+    /// Whether the whole workspace is being documented.
+    #[inline]
+    pub fn workspace(&self) -> bool {
+        self.workspace
+    }
+
+    /// Whether the output should be reproducible.
+    #[inline]
+    pub fn reproducible(&self) -> bool {
+        self.reproducible
+    }
+
+    /// Whether to analyze the files of the described packages.
+    #[inline]
+    pub fn with_files(&self) -> bool {
+        self.with_files
+    }
+
+    /// Get the maximum number of files to analyze in each package.
+    #[inline]
+    pub fn max_files(&self) -> usize {
+        self.max_files
+    }
+
+    /// Get the algorithms to checksum analyzed files with, always including SHA-1.
+    #[inline]
+    pub fn checksum_algorithms(&self) -> Vec<Algorithm> {
+        let mut algorithms = vec![Algorithm::Sha1];
+
+        let selected = if self.checksum_algorithms.is_empty() {
+            &[Algorithm::Sha1, Algorithm::Sha256][..]
+        } else {
+            &self.checksum_algorithms
+        };
+
+        for algorithm in selected {
+            if algorithms.contains(algorithm).not() {
+                algorithms.push(*algorithm);
+            }
+        }
+
+        algorithms
+    }
+
+    /// Get the types of relationships to include, every type if none were given.
+    #[inline]
+    pub fn relationship_types(&self) -> &[RelationshipType] {
+        if self.relationships.is_empty() {
+            &[
+                RelationshipType::Describes,
+                RelationshipType::DependsOn,
+                RelationshipType::Contains,
+                RelationshipType::BuildDependencyOf,
+            ]
+        } else {
+            &self.relationships
+        }
+    }
+
+    /// Whether to leave out the relationship saying the document describes the root package.
+    #[inline]
+    pub fn no_root_describes(&self) -> bool {
+        self.no_root_describes
+    }
+
+    /// Get the number of threads used to analyze files.
+    #[inline]
+    pub fn jobs(&self) -> usize {
+        self.jobs
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+    }
+
+    /// Get the directory analyzed files are named relative to, if one was given.
+    #[inline]
+    pub fn strip_prefix(&self) -> Option<&Path> {
+        self.strip_prefix.as_deref()
+    }
+
+    /// Get the types given to analyzed files with particular extensions.
+    #[inline]
+    pub fn file_type_overrides(&self) -> FileTypeOverrides {
+        let mut overrides = FileTypeOverrides::new();
+
+        for (extension, file_type) in &self.file_type_override {
+            let types = overrides.entry(extension.clone()).or_default();
+
+            if types.contains(file_type).not() {
+                types.push(*file_type);
+            }
+        }
+
+        overrides
+    }
+
+    /// Whether to record the contributors to each analyzed file.
+    #[inline]
+    pub fn file_contributors(&self) -> bool {
+        self.file_contributors
+    }
+
+    /// Whether to annotate packages whose version was yanked from crates.io.
+    #[inline]
+    pub fn check_yanked(&self) -> bool {
+        self.check_yanked
+    }
+
+    /// Whether to annotate the document with the SHA-256 of `Cargo.lock`.
+    #[inline]
+    pub fn annotate_lock_hash(&self) -> bool {
+        self.annotate_lock_hash
+    }
+
+    /// Whether the concluded license of each package is its declared license.
+    #[inline]
+    pub fn conclude_declared(&self) -> bool {
+        self.conclude_declared
+    }
+
+    /// Whether to rewrite declared licenses with official SPDX identifiers.
+    #[inline]
+    pub fn normalize_licenses(&self) -> bool {
+        self.normalize_licenses
+    }
+
+    /// Get the path of the prior document to merge conclusions from, if any.
+    #[inline]
+    pub fn merge_existing(&self) -> Option<&Path> {
+        self.merge_existing.as_deref()
+    }
+
+    /// Get the most packages allowed to have an unknown license, if there's a limit.
+    #[inline]
+    pub fn max_unknown_licenses(&self) -> Option<usize> {
+        if self.fail_on_unknown_license {
+            Some(0)
+        } else {
+            self.max_unknown_licenses
+        }
+    }
+
+    /// Whether to check the document against the SPDX 2.3 JSON schema.
+    #[inline]
+    pub fn validate(&self) -> bool {
+        self.validate
+    }
+
+    /// Get the licenses packages are permitted to be under, or nothing if any license is.
+    #[inline]
+    pub fn allowed_licenses(&self) -> &[String] {
+        &self.allowed_licenses
+    }
+
+    /// Get the licenses packages are forbidden to be under.
+    #[inline]
+    pub fn denied_licenses(&self) -> &[String] {
+        &self.denied_licenses
+    }
+
+    /// Whether to record the feature resolver version in the document comment.
+    #[inline]
+    pub fn comment_with_resolver(&self) -> bool {
+        self.comment_with_resolver
+    }
+
+    /// Whether to record the command line in the document comment.
+    #[inline]
+    pub fn record_invocation(&self) -> bool {
+        self.record_invocation
+    }
+
+    /// Get the extra words marking arguments as secrets to redact from the command line.
+    #[inline]
+    pub fn redacted_words(&self) -> &[String] {
+        &self.redacted_words
+    }
+
+    /// Get the type of the package URLs of packages.
+    #[inline]
+    pub fn purl_type(&self) -> &str {
+        &self.purl_type
+    }
+
+    /// Get the namespace of the package URLs of packages, if one was given.
+    #[inline]
+    pub fn purl_namespace(&self) -> Option<&str> {
+        self.purl_namespace.as_deref()
+    }
+
+    /// Whether to record path dependencies as the crates.io packages they're published as.
+    #[inline]
+    pub fn strip_path_dependencies(&self) -> bool {
+        self.strip_path_dependencies
+    }
+
+    /// Whether to reference security advisories affecting packages.
+    #[inline]
+    pub fn with_advisories(&self) -> bool {
+        self.with_advisories
+    }
+
+    /// Get the program the document is passed through before it's written out, if any.
+    #[inline]
+    pub fn post_process_script(&self) -> Option<&Path> {
+        self.post_process_script.as_deref()
+    }
+
+    /// Get the command to run instead of generating an SBOM, if any.
+    #[inline]
+    pub fn command(&self) -> Option<&Command> {
+        self.command.as_ref()
+    }
+
+    /// Whether only errors are reported.
+    #[inline]
+    pub fn quiet(&self) -> bool {
+        self.quiet
+    }
+
+    /// Get the log level picked by the user, if any.
+    #[inline]
+    pub fn log_level(&self) -> Option<LevelFilter> {
+        match (self.quiet, self.verbose) {
+            (true, _) => Some(LevelFilter::Error),
+            (false, 0) => None,
+            (false, 1) => Some(LevelFilter::Info),
+            (false, 2) => Some(LevelFilter::Debug),
+            (false, _) => Some(LevelFilter::Trace),
+        }
+    }
+
     /// Check if the command is running interactively.
     #[inline]
     pub fn is_interactive(&self) -> bool {
         self.no_interact.not()
     }
+
+    /// Fill in the settings which weren't given on the CLI from a configuration.
+    ///
+    /// A format from the configuration is only used if the output file extension doesn't
+    /// imply one.
+    fn apply_config(&mut self, config: Config) -> Result<()> {
+        if self.host_url.is_none() && self.namespace.is_none() {
+            self.host_url = config.host_url;
+        }
+
+        if self.creator.is_none() {
+            self.creator = config.creator;
+        }
+
+        if self.format.is_none() && self.inferred_format().is_none() {
+            self.format = config.format.as_deref().map(parse_format).transpose()?;
+        }
+
+        if self.checksum_algorithms.is_empty() {
+            for algorithm in config.checksum_algorithms.unwrap_or_default() {
+                self.checksum_algorithms.push(
+                    <Algorithm as ArgEnum>::from_str(&algorithm, true).map_err(|e| {
+                        anyhow!("invalid checksum algorithm in configuration: {}", e)
+                    })?,
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::options;
+
+    /// Get the error from the host URL of the options given by the arguments, if any.
+    fn host_url_error(args: &[&str]) -> Option<String> {
+        let mut all_args = vec!["--no-interact"];
+        all_args.extend_from_slice(args);
+
+        options(&all_args)
+            .host_url()
+            .err()
+            .map(|error| error.to_string())
+    }
+
+    #[test]
+    fn missing_host_url_is_rejected() {
+        let error = host_url_error(&[]).unwrap();
+        assert!(error.contains("--host-url must be specified"), "{}", error);
+    }
+
+    #[test]
+    fn empty_host_url_is_rejected() {
+        let error = host_url_error(&["--host-url", ""]).unwrap();
+        assert!(error.contains("must be an absolute URL"), "{}", error);
+    }
+
+    #[test]
+    fn relative_host_url_is_rejected() {
+        let error = host_url_error(&["--host-url", "sboms/fixture"]).unwrap();
+        assert!(error.contains("must be an absolute URL"), "{}", error);
+        assert!(error.contains("'sboms/fixture'"), "{}", error);
+    }
+
+    #[test]
+    fn well_formed_host_url_is_accepted() {
+        let args = ["--no-interact", "--host-url", "https://example.com/sboms"];
+        assert_eq!(
+            options(&args).host_url().unwrap(),
+            "https://example.com/sboms"
+        );
+    }
+
+    #[test]
+    fn also_formats_must_differ_from_the_output_format() {
+        let error = check_also_formats(&options(&["--format", "json", "--also-format", "json"]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("--also-format JSON"), "{}", error);
+
+        assert!(check_also_formats(&options(&["--also-format", "kv"])).is_err());
+        assert!(check_also_formats(&options(&["--also-format", "json"])).is_ok());
+    }
+
+    #[test]
+    fn also_formats_must_differ_from_each_other() {
+        let args = [
+            "--also-format",
+            "json",
+            "--also-format",
+            "yaml",
+            "--also-format",
+            "json",
+        ];
+        assert!(check_also_formats(&options(&args)).is_err());
+    }
+
+    #[test]
+    fn file_type_overrides_are_parsed() {
+        assert_eq!(
+            parse_file_type_override("md=TEXT").unwrap(),
+            (String::from("md"), FileType::Text)
+        );
+        assert_eq!(
+            parse_file_type_override(".MD=documentation").unwrap(),
+            (String::from("md"), FileType::Documentation)
+        );
+        assert!(parse_file_type_override("md").is_err());
+        assert!(parse_file_type_override("md=PROSE").is_err());
+    }
+
+    #[test]
+    fn repeated_file_type_overrides_give_several_types() {
+        let overrides = options(&[
+            "--file-type-override",
+            "md=TEXT",
+            "--file-type-override",
+            "md=DOCUMENTATION",
+            "--file-type-override",
+            "md=TEXT",
+        ])
+        .file_type_overrides();
+
+        assert_eq!(overrides["md"], [FileType::Text, FileType::Documentation]);
+    }
 }
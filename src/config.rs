@@ -0,0 +1,122 @@
+//! Reads defaults for the CLI args from configuration files.
+//!
+//! Settings are taken from, in order of precedence:
+//!
+//! 1. The CLI args.
+//! 2. `.cargo-spdx.toml` in the workspace root or its nearest ancestor with one, so it's
+//!    found from any directory inside the workspace.
+//! 3. `cargo-spdx/config.toml` in `$XDG_CONFIG_HOME`, or `~/.config` if that's unset.
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::ops::Not as _;
+use std::path::{Path, PathBuf};
+
+/// The name of the configuration file found in the workspace.
+const WORKSPACE_CONFIG: &str = ".cargo-spdx.toml";
+
+/// Defaults for the CLI args.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The URL where SBOMs will be hosted.
+    pub host_url: Option<String>,
+    /// The person creating SBOMs, as 'Name <email>'.
+    pub creator: Option<String>,
+    /// The output format to use.
+    pub format: Option<String>,
+    /// The algorithms to checksum analyzed files with.
+    pub checksum_algorithms: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Load the configuration, merging the workspace file found from `workspace_root` over the
+    /// user's file.
+    pub fn load(workspace_root: &Path) -> Result<Self> {
+        let user = match user_config_path() {
+            Some(path) => Config::read(&path)?.unwrap_or_default(),
+            None => Config::default(),
+        };
+
+        match workspace_config_path(workspace_root) {
+            Some(path) => Ok(Config::read(&path)?.unwrap_or_default().or(user)),
+            None => Ok(user),
+        }
+    }
+
+    /// Read a configuration file, if it exists.
+    fn read(path: &Path) -> Result<Option<Self>> {
+        if path.is_file().not() {
+            return Ok(None);
+        }
+
+        log::info!(target: "cargo_spdx", "reading configuration from '{}'", path.display());
+
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read '{}': {}", path.display(), e))?;
+
+        toml::from_str(&contents)
+            .map(Some)
+            .map_err(|e| anyhow!("invalid configuration in '{}': {}", path.display(), e))
+    }
+
+    /// Fill in the settings missing from this configuration from another.
+    fn or(self, other: Config) -> Self {
+        Config {
+            host_url: self.host_url.or(other.host_url),
+            creator: self.creator.or(other.creator),
+            format: self.format.or(other.format),
+            checksum_algorithms: self.checksum_algorithms.or(other.checksum_algorithms),
+        }
+    }
+}
+
+/// Get the path of the user's configuration file.
+fn user_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+    Some(config_home.join("cargo-spdx").join("config.toml"))
+}
+
+/// Find the workspace configuration file in the workspace root or its ancestors.
+fn workspace_config_path(workspace_root: &Path) -> Option<PathBuf> {
+    workspace_root
+        .ancestors()
+        .map(|dir| dir.join(WORKSPACE_CONFIG))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn workspace_config_is_found_from_the_workspace_root() {
+        let dir = TempDir::new();
+        let path = dir.write(
+            ".cargo-spdx.toml",
+            "creator = \"Test <test@example.com>\"\n",
+        );
+        let workspace_root = dir.path().join("workspace");
+        fs::create_dir_all(&workspace_root).unwrap();
+
+        assert_eq!(workspace_config_path(&workspace_root), Some(path.clone()));
+        assert_eq!(
+            Config::read(&path).unwrap().unwrap().creator.as_deref(),
+            Some("Test <test@example.com>")
+        );
+    }
+
+    #[test]
+    fn workspace_config_isnt_found_in_members() {
+        let dir = TempDir::new();
+        dir.write("member/.cargo-spdx.toml", "creator = \"Member\"\n");
+
+        assert_eq!(workspace_config_path(dir.path()), None);
+    }
+}
@@ -0,0 +1,219 @@
+//! Compares the dependency graphs of two git refs.
+
+use crate::cargo::{find_workspace_root, CrateMetadata};
+use crate::document::{get_relationships, PackageRefs, SpdxRef};
+use crate::git::checkout_ref;
+use crate::verify::{collect_versions, diff_versions};
+use crate::GenerateOptions;
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Describe the packages added, removed, or changed version, and the relationships added or
+/// removed, between two git refs.
+///
+/// Each ref is checked out into a temporary directory and resolved the same way as when
+/// generating a document, so only the packages which would be documented are compared. If
+/// `to` is missing, the working tree is compared instead, including any uncommitted changes.
+/// Otherwise only what's committed at each ref is compared, so a dirty working tree makes no
+/// difference.
+pub fn diff_refs(options: &GenerateOptions, from: &str, to: Option<&str>) -> Result<Vec<String>> {
+    let workspace_root = find_workspace_root(options)?;
+
+    let before = Graph::at_ref(options, &workspace_root, from, "from")?;
+    let after = match to {
+        Some(to) => Graph::at_ref(options, &workspace_root, to, "to")?,
+        None => Graph::new(options, &CrateMetadata::load(options)?)?,
+    };
+
+    let mut differences = diff_versions(
+        &collect_versions(before.versions),
+        &collect_versions(after.versions),
+    );
+
+    differences.extend(
+        before
+            .relationships
+            .difference(&after.relationships)
+            .map(|relationship| format!("removed: {}", relationship)),
+    );
+    differences.extend(
+        after
+            .relationships
+            .difference(&before.relationships)
+            .map(|relationship| format!("added: {}", relationship)),
+    );
+
+    Ok(differences)
+}
+
+/// The packages and relationships which would be documented for one revision of a crate.
+#[derive(Debug)]
+struct Graph {
+    /// The name and version of each package.
+    versions: Vec<(String, String)>,
+    /// The relationships between packages, as they'd be written to a document.
+    relationships: BTreeSet<String>,
+}
+
+impl Graph {
+    /// Resolve the graph of the crate as of a git ref, checking it out into a temporary
+    /// directory named with `label`.
+    fn at_ref(
+        options: &GenerateOptions,
+        workspace_root: &Path,
+        git_ref: &str,
+        label: &str,
+    ) -> Result<Self> {
+        let checkout = Checkout::new(label);
+        let root = checkout_ref(workspace_root, git_ref, checkout.path())?;
+        let metadata = CrateMetadata::load_manifest(options, &root.join("Cargo.toml"))?;
+
+        Graph::new(options, &metadata)
+    }
+
+    /// Collect the graph from the crate metadata.
+    fn new(options: &GenerateOptions, metadata: &CrateMetadata) -> Result<Self> {
+        let versions = metadata
+            .packages()
+            .map(|package| (package.name.clone(), package.version.to_string()))
+            .collect();
+
+        // Relationships to the document only restate which packages are described.
+        let refs = PackageRefs::new(metadata);
+        let relationships = get_relationships(options, metadata, &refs, &[])?
+            .into_iter()
+            .filter(|relationship| relationship.spdx_element_id != SpdxRef::document())
+            .map(|relationship| relationship.to_string())
+            .collect();
+
+        Ok(Graph {
+            versions,
+            relationships,
+        })
+    }
+}
+
+/// A temporary directory a git ref is checked out into, removed when it's dropped.
+#[derive(Debug)]
+struct Checkout(PathBuf);
+
+impl Checkout {
+    /// Pick a directory to check out a git ref into, unique to this process and `label`.
+    fn new(label: &str) -> Self {
+        Checkout(env::temp_dir().join(format!("cargo-spdx-diff-{}-{}", process::id(), label)))
+    }
+
+    /// Get the path of the directory.
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{options, TempDir};
+    use cargo_metadata::MetadataCommand;
+    use git2::{Repository, Signature};
+
+    /// The manifest of the fixture crate, depending on the given path dependencies.
+    fn manifest(dependencies: &[&str]) -> String {
+        let mut manifest = String::from(
+            "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\n",
+        );
+
+        for dependency in dependencies {
+            manifest.push_str(&format!(
+                "{} = {{ path = \"{}\" }}\n",
+                dependency, dependency
+            ));
+        }
+
+        manifest
+    }
+
+    /// Write a dependency of the fixture crate.
+    fn write_dependency(dir: &TempDir, name: &str, version: &str) {
+        dir.write(
+            &format!("{}/Cargo.toml", name),
+            format!(
+                "[package]\nname = \"{}\"\nversion = \"{}\"\nedition = \"2021\"\n",
+                name, version
+            ),
+        );
+        dir.write(&format!("{}/src/lib.rs", name), "");
+    }
+
+    /// Commit everything in the working tree, tagging the commit.
+    fn commit(repo: &Repository, tag: &str) {
+        // PANIC SAFETY: The repository was just made, and everything in it can be added.
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Fixture", "fixture@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents = parent.iter().collect::<Vec<_>>();
+
+        let commit = repo
+            .commit(Some("HEAD"), &signature, &signature, tag, &tree, &parents)
+            .unwrap();
+        repo.tag_lightweight(tag, &repo.find_object(commit, None).unwrap(), false)
+            .unwrap();
+    }
+
+    #[test]
+    fn both_revisions_are_resolved_and_compared() {
+        let dir = TempDir::new();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        dir.write("Cargo.toml", manifest(&["alpha", "beta"]));
+        dir.write("src/lib.rs", "");
+        write_dependency(&dir, "alpha", "0.1.0");
+        write_dependency(&dir, "beta", "0.1.0");
+        commit(&repo, "before");
+
+        dir.write("Cargo.toml", manifest(&["alpha", "gamma"]));
+        write_dependency(&dir, "alpha", "0.2.0");
+        write_dependency(&dir, "gamma", "0.1.0");
+        commit(&repo, "after");
+
+        // The metadata file only points `diff` at the workspace.
+        let metadata = MetadataCommand::new()
+            .manifest_path(dir.path().join("Cargo.toml"))
+            .other_options(vec![String::from("--offline")])
+            .exec()
+            .unwrap();
+        let path = dir.write("metadata.json", serde_json::to_string(&metadata).unwrap());
+        let options = options(&["--offline", "--metadata", path.to_str().unwrap()]);
+
+        let differences = diff_refs(&options, "before", Some("after")).unwrap();
+
+        assert_eq!(
+            differences,
+            [
+                "changed: alpha 0.1.0 -> 0.2.0",
+                "removed: beta 0.1.0",
+                "added: gamma 0.1.0",
+                "removed: SPDXRef-fixture-0.1.0 DEPENDS_ON SPDXRef-alpha-0.1.0",
+                "removed: SPDXRef-fixture-0.1.0 DEPENDS_ON SPDXRef-beta-0.1.0",
+                "added: SPDXRef-fixture-0.1.0 DEPENDS_ON SPDXRef-alpha-0.2.0",
+                "added: SPDXRef-fixture-0.1.0 DEPENDS_ON SPDXRef-gamma-0.1.0",
+            ]
+        );
+    }
+}
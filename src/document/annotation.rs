@@ -0,0 +1,49 @@
+//! Defines the annotations section of the SPDX document.
+
+use crate::document::package::SpdxRef;
+use crate::document::{Created, Creator, TOOL};
+use derive_more::Display;
+
+/// A comment about an element of the SPDX document.
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    /// Who made the annotation.
+    pub annotator: Creator,
+    /// When the annotation was made.
+    pub annotation_date: Created,
+    /// The type of the annotation.
+    pub annotation_type: AnnotationType,
+    /// The element the annotation is about.
+    pub spdx_identifier_reference: SpdxRef,
+    /// The annotation itself.
+    pub annotation_comment: String,
+}
+
+impl Annotation {
+    /// Construct a new annotation made by this tool.
+    pub fn new(
+        annotation_date: &Created,
+        annotation_type: AnnotationType,
+        spdx_identifier_reference: SpdxRef,
+        annotation_comment: String,
+    ) -> Self {
+        Annotation {
+            annotator: Creator::tool(TOOL),
+            annotation_date: annotation_date.clone(),
+            annotation_type,
+            spdx_identifier_reference,
+            annotation_comment,
+        }
+    }
+}
+
+/// The type of an annotation.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq)]
+pub enum AnnotationType {
+    /// A review of the element.
+    #[display(fmt = "REVIEW")]
+    Review,
+    /// Any annotation which isn't a review.
+    #[display(fmt = "OTHER")]
+    Other,
+}
@@ -0,0 +1,119 @@
+//! Builds a document from the dependency information embedded in a prebuilt binary.
+
+use crate::binary::AuditableInfo;
+use crate::document::file::{FileInformation, PackageVerificationCode};
+use crate::document::package::{unique_refs, PurlPrefix, SpdxRef};
+use crate::document::{
+    get_created, get_creator, get_document_namespace, CreatorComment, Document, DocumentBuilder,
+    DocumentComment, PackageInformation, Relationship, RelationshipType, LICENSE_LIST_VERSION,
+};
+use crate::GenerateOptions;
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+/// Build a new SPDX document for the binary at `path`, from the dependency information
+/// `cargo auditable` embedded in it.
+///
+/// The document describes the root package the binary was built from, which contains the
+/// binary itself.
+pub fn build_from_binary(
+    args: &GenerateOptions,
+    output_file_name: &str,
+    path: &Path,
+) -> Result<Document> {
+    log::info!(target: "cargo_spdx", "building the document from a binary");
+
+    let info = AuditableInfo::read(path)?;
+    let root = info
+        .root()
+        .ok_or_else(|| anyhow!("'{}' records no packages", path.display()))?;
+
+    let refs = unique_refs(
+        info.packages
+            .iter()
+            .map(|package| SpdxRef::new(&format!("{}-{}", package.name, package.version)))
+            .collect(),
+    );
+
+    let purl_prefix = PurlPrefix::from_args(args);
+    let mut packages = info
+        .packages
+        .iter()
+        .zip(&refs)
+        .map(|(package, id)| {
+            PackageInformation::from_auditable_package(package, id.clone(), purl_prefix)
+        })
+        .collect::<Vec<_>>();
+
+    let binary = FileInformation::try_from_binary(
+        path,
+        SpdxRef::new(&format!("{}-File-0", refs[root].0)),
+        &args.checksum_algorithms(),
+        &args.file_type_overrides(),
+    )?;
+
+    let mut relationships = vec![
+        Relationship::new(
+            SpdxRef::document(),
+            RelationshipType::Describes,
+            refs[root].clone(),
+        ),
+        Relationship::new(
+            refs[root].clone(),
+            RelationshipType::Contains,
+            binary.file_spdx_identifier.clone(),
+        ),
+    ];
+
+    let root_package = &mut packages[root];
+    root_package.files = vec![binary];
+    root_package.files_analyzed = true;
+    root_package.package_verification_code =
+        Some(PackageVerificationCode::from_files(&root_package.files));
+
+    for (package, id) in info.packages.iter().zip(&refs) {
+        for dep in &package.dependencies {
+            relationships.push(Relationship::new(
+                id.clone(),
+                RelationshipType::DependsOn,
+                refs[*dep].clone(),
+            ));
+
+            // Build dependencies are only used to build the binary, never shipped in it.
+            if info.packages[*dep].kind.as_deref() == Some("build") {
+                relationships.push(Relationship::new(
+                    refs[*dep].clone(),
+                    RelationshipType::BuildDependencyOf,
+                    id.clone(),
+                ));
+            }
+        }
+    }
+
+    let types = args.relationship_types();
+    relationships.retain(|relationship| types.contains(&relationship.relationship_type));
+
+    // The binary's package may instead be described by a document embedding this one.
+    if args.no_root_describes() {
+        relationships.retain(|relationship| relationship.spdx_element_id != SpdxRef::document());
+    }
+
+    // Reproducible output can't depend on the order packages were recorded in.
+    if args.reproducible() {
+        packages.sort_by(|a, b| a.package_spdx_identifier.cmp(&b.package_spdx_identifier));
+        relationships.sort();
+    }
+
+    Ok(DocumentBuilder::default()
+        .spdx_version(args.spdx_version())
+        .document_name(output_file_name)
+        .document_namespace(get_document_namespace(args, output_file_name, &packages)?)
+        .creator(get_creator(args)?)
+        .created(get_created(args)?)
+        .license_list_version(LICENSE_LIST_VERSION)
+        .creator_comment(args.creator_comment().map(CreatorComment::from))
+        .document_comment(args.document_comment().map(DocumentComment::from))
+        .package_information(packages)
+        .relationships(relationships)
+        .build()?)
+}
@@ -0,0 +1,151 @@
+//! Defines checksums and how they're calculated.
+
+use anyhow::Result;
+use clap::ArgEnum;
+use derive_more::Display;
+use md5::Md5;
+use sha1::digest::DynDigest;
+use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha512};
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+
+/// The size of the chunks files are read in while being hashed.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+thread_local! {
+    /// The buffer chunks are read into, reused for every file hashed on the thread.
+    static BUFFER: RefCell<Vec<u8>> = RefCell::new(vec![0; CHUNK_SIZE]);
+}
+
+/// A checksum of a file.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+#[display(fmt = "{}: {}", algorithm, value)]
+pub struct Checksum {
+    /// The algorithm used to calculate the checksum.
+    pub algorithm: Algorithm,
+    /// The checksum as a lowercase hexadecimal string.
+    pub value: String,
+}
+
+/// An algorithm used to calculate a checksum.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, ArgEnum)]
+pub enum Algorithm {
+    /// SHA-1, which SPDX requires for every file.
+    #[display(fmt = "SHA1")]
+    Sha1,
+    /// SHA-256.
+    #[display(fmt = "SHA256")]
+    Sha256,
+    /// SHA-512.
+    #[display(fmt = "SHA512")]
+    Sha512,
+    /// MD5, which is broken and only supported for legacy verification systems.
+    #[display(fmt = "MD5")]
+    Md5,
+}
+
+impl Algorithm {
+    /// Get a new hasher for the algorithm.
+    fn hasher(self) -> Box<dyn DynDigest> {
+        match self {
+            Algorithm::Sha1 => Box::new(Sha1::new()),
+            Algorithm::Sha256 => Box::new(Sha256::new()),
+            Algorithm::Sha512 => Box::new(Sha512::new()),
+            Algorithm::Md5 => Box::new(Md5::new()),
+        }
+    }
+}
+
+/// Calculate the checksums of a file with each of the given algorithms.
+///
+/// The file is read once, in fixed-size chunks, with each chunk fed to every hasher. Chunks
+/// are read into a buffer reused across files, so memory use doesn't grow with file size or
+/// with the number of files hashed.
+pub fn calculate_checksums(path: &Path, algorithms: &[Algorithm]) -> Result<Vec<Checksum>> {
+    let mut file = File::open(path)?;
+    let mut hashers = algorithms
+        .iter()
+        .map(|algorithm| (*algorithm, algorithm.hasher()))
+        .collect::<Vec<_>>();
+
+    BUFFER.with(|buffer| -> Result<()> {
+        let mut buffer = buffer.borrow_mut();
+
+        loop {
+            let read = match file.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(read) => read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            for (_, hasher) in &mut hashers {
+                hasher.update(&buffer[..read]);
+            }
+        }
+    })?;
+
+    Ok(hashers
+        .into_iter()
+        .map(|(algorithm, hasher)| Checksum {
+            algorithm,
+            value: hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn sha512_matches_known_digest() {
+        let dir = TempDir::new();
+        let path = dir.write("abc.txt", "abc");
+
+        let checksums = calculate_checksums(&path, &[Algorithm::Sha512]).unwrap();
+
+        assert_eq!(
+            checksums,
+            vec![Checksum {
+                algorithm: Algorithm::Sha512,
+                value: String::from(
+                    "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                     2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+                ),
+            }]
+        );
+    }
+
+    #[test]
+    fn large_files_are_hashed_in_chunks() {
+        // Several megabytes, ending partway through a chunk.
+        let contents = (0..5 * 1024 * 1024 + 7)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        let dir = TempDir::new();
+        let path = dir.write("large.bin", &contents);
+
+        let checksums = calculate_checksums(&path, &[Algorithm::Sha1, Algorithm::Sha256]).unwrap();
+
+        let hex = |digest: &[u8]| {
+            digest
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        };
+        assert_eq!(checksums[0].value, hex(&Sha1::digest(&contents)));
+        assert_eq!(checksums[1].value, hex(&Sha256::digest(&contents)));
+
+        // Every chunk is read into the same buffer, which never grows.
+        BUFFER.with(|buffer| assert_eq!(buffer.borrow().len(), CHUNK_SIZE));
+    }
+}
@@ -0,0 +1,68 @@
+//! Reads the packages back out of a previously generated SPDX document.
+
+use crate::document::merge::read_tag_values;
+use crate::format::Format;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Read the name and version of each package in a key-value or JSON document.
+///
+/// Packages without a version have an empty one.
+pub fn read_package_versions(path: &Path) -> Result<Vec<(String, String)>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read '{}': {}", path.display(), e))?;
+
+    match Format::from_path(path) {
+        Some(Format::KeyValue) => Ok(read_key_value(&contents)),
+        Some(Format::Json) => read_json(&contents)
+            .map_err(|e| anyhow!("invalid SPDX document '{}': {}", path.display(), e)),
+        _ => Err(anyhow!(
+            "only {} and {} documents can be read, but '{}' isn't one",
+            Format::KeyValue,
+            Format::Json,
+            path.display()
+        )),
+    }
+}
+
+/// Read the packages of a key-value document.
+fn read_key_value(contents: &str) -> Vec<(String, String)> {
+    let mut packages: Vec<(String, String)> = vec![];
+
+    for (tag, value) in read_tag_values(contents) {
+        match tag.as_str() {
+            "PackageName" => packages.push((value, String::new())),
+            "PackageVersion" => {
+                if let Some((_, version)) = packages.last_mut() {
+                    *version = value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    packages
+}
+
+/// Read the packages of a JSON document.
+fn read_json(contents: &str) -> Result<Vec<(String, String)>> {
+    let document: Value = serde_json::from_str(contents)?;
+
+    let packages = document["packages"]
+        .as_array()
+        .ok_or_else(|| anyhow!("no packages found"))?;
+
+    packages
+        .iter()
+        .map(|package| {
+            let name = package["name"]
+                .as_str()
+                .ok_or_else(|| anyhow!("package without a name"))?;
+            let version = package["versionInfo"].as_str().unwrap_or_default();
+
+            Ok((name.to_owned(), version.to_owned()))
+        })
+        .collect()
+}
@@ -0,0 +1,603 @@
+//! Defines the file information section of the SPDX document.
+
+use crate::document::checksum::{calculate_checksums, Algorithm, Checksum};
+use crate::document::license::scan_license_identifiers;
+use crate::document::package::{LicenseExpression, SpdxRef, SpdxValue};
+use anyhow::{anyhow, Result};
+use cargo_metadata::Package;
+use clap::ArgEnum;
+use derive_more::Display;
+use ignore::WalkBuilder;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::ops::Not as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Information about a single file in a package.
+#[derive(Debug, Clone)]
+pub struct FileInformation {
+    /// The path of the file, relative to the package directory.
+    pub file_name: String,
+
+    /// The identifier for the file within the document.
+    pub file_spdx_identifier: SpdxRef,
+
+    /// The types of the file.
+    pub file_types: Vec<FileType>,
+
+    /// Checksums of the file's contents.
+    pub file_checksum: Vec<Checksum>,
+
+    /// The license the SPDX file creator concluded the file has.
+    pub concluded_license: SpdxValue<LicenseExpression>,
+
+    /// The licenses named by the file's `SPDX-License-Identifier` tags.
+    pub license_information_in_file: Vec<String>,
+
+    /// Copyright notices for the file.
+    pub copyright_text: SpdxValue<String>,
+
+    /// The people who contributed to the file.
+    pub file_contributor: Vec<String>,
+}
+
+impl FileInformation {
+    /// Analyze a file, as set out by `analysis`.
+    ///
+    /// The file is named relative to the strip prefix, or by its absolute path if it's outside
+    /// of it.
+    fn analyze(
+        path: &Path,
+        file_spdx_identifier: SpdxRef,
+        analysis: &FileAnalysis<'_>,
+    ) -> Result<Self> {
+        let file_name = match path.strip_prefix(analysis.strip_prefix) {
+            Ok(relative_path) => format!("./{}", relative_path.display()),
+            Err(_) => path.display().to_string(),
+        };
+        let file_types = detect_file_type(path, analysis.overrides);
+
+        // Binary files won't have license tags.
+        let license_information_in_file = if file_types.contains(&FileType::Binary) {
+            vec![]
+        } else {
+            scan_license_identifiers(path)
+        };
+
+        Ok(FileInformation {
+            file_name,
+            file_spdx_identifier,
+            file_types,
+            file_checksum: calculate_checksums(path, analysis.algorithms)?,
+            concluded_license: SpdxValue::NoAssertion,
+            license_information_in_file,
+            copyright_text: SpdxValue::NoAssertion,
+            file_contributor: vec![],
+        })
+    }
+
+    /// Analyze a prebuilt binary, checksumming it with each of `algorithms`.
+    ///
+    /// The file is named after the binary alone, without the directory it's in. It's a binary
+    /// unless `overrides` say otherwise for its extension.
+    pub fn try_from_binary(
+        path: &Path,
+        file_spdx_identifier: SpdxRef,
+        algorithms: &[Algorithm],
+        overrides: &FileTypeOverrides,
+    ) -> Result<Self> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| anyhow!("'{}' isn't a file", path.display()))?;
+
+        Ok(FileInformation {
+            file_name: format!("./{}", file_name.to_string_lossy()),
+            file_spdx_identifier,
+            file_types: get_extension(path)
+                .and_then(|extension| overrides.get(&extension))
+                .cloned()
+                .unwrap_or_else(|| vec![FileType::Binary]),
+            file_checksum: calculate_checksums(path, algorithms)?,
+            concluded_license: SpdxValue::NoAssertion,
+            license_information_in_file: vec![],
+            copyright_text: SpdxValue::NoAssertion,
+            file_contributor: vec![],
+        })
+    }
+
+    /// Get the SHA1 checksum of the file.
+    fn sha1(&self) -> Option<&str> {
+        self.file_checksum
+            .iter()
+            .find(|checksum| checksum.algorithm == Algorithm::Sha1)
+            .map(|checksum| checksum.value.as_str())
+    }
+}
+
+/// How many files are hashed between each progress message.
+const PROGRESS_INTERVAL: usize = 500;
+
+/// How the files of packages are analyzed.
+#[derive(Debug)]
+pub struct FileAnalysis<'a> {
+    /// The threads files are analyzed on.
+    pub pool: &'a ThreadPool,
+    /// Cargo's target directory, which is skipped wherever it's been moved to.
+    pub target_dir: &'a Path,
+    /// The most files analyzed in each package.
+    pub max_files: usize,
+    /// The algorithms files are checksummed with.
+    pub algorithms: &'a [Algorithm],
+    /// The types of files with particular extensions, replacing the default types.
+    pub overrides: &'a FileTypeOverrides,
+    /// The directory files are named relative to.
+    pub strip_prefix: &'a Path,
+}
+
+/// Enumerate and analyze the files in a package's directory, as set out by `analysis`.
+///
+/// Files ignored by `.gitignore`, hidden files, `target/` directories, Cargo's target
+/// directory wherever it's been moved to, and the directories of other packages nested
+/// inside this one are skipped. Fails if the package has more than `analysis.max_files`
+/// files, since the package verification code can't be calculated from only some of them.
+/// Files outside the strip prefix keep their absolute paths, with a warning.
+pub fn get_package_files(
+    package: &Package,
+    package_spdx_identifier: &SpdxRef,
+    analysis: &FileAnalysis<'_>,
+) -> Result<Vec<FileInformation>> {
+    // Both the package directory and the strip prefix are canonical, so a symlink on the way
+    // to either doesn't put every file outside the prefix.
+    let package_dir = match package.manifest_path.parent() {
+        Some(dir) => canonicalize(dir.as_std_path()),
+        None => return Ok(vec![]),
+    };
+
+    log::info!(target: "cargo_spdx", "analyzing files of package '{}'", package.name);
+
+    if package_dir.starts_with(analysis.strip_prefix).not() {
+        log::warn!(
+            target: "cargo_spdx",
+            "package '{}' is outside '{}', so its files are recorded with absolute paths",
+            package.name,
+            analysis.strip_prefix.display()
+        );
+    }
+
+    // The filter has to own everything it uses.
+    let target_dir = canonicalize(analysis.target_dir);
+
+    let walk = WalkBuilder::new(&package_dir)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().map_or(false, |t| t.is_dir());
+            let is_skipped = entry.file_name() == "target"
+                || entry.path() == target_dir
+                || entry.path().join("Cargo.toml").is_file();
+
+            (is_dir && entry.depth() > 0 && is_skipped).not()
+        })
+        .build();
+
+    let mut paths = vec![];
+
+    for entry in walk {
+        let entry = entry?;
+
+        if entry.file_type().map_or(true, |t| t.is_file().not()) {
+            continue;
+        }
+
+        if paths.len() == analysis.max_files {
+            return Err(anyhow!(
+                "package '{}' has more than {} files, raise '--max-files' to analyze them all",
+                package.name,
+                analysis.max_files
+            ));
+        }
+
+        paths.push(entry.into_path());
+    }
+
+    // Hashing dominates the time taken, so files are analyzed on the worker threads. Collecting
+    // keeps the files in the order they were walked in, whichever thread finishes first.
+    let hashed = AtomicUsize::new(0);
+
+    let files = analysis.pool.install(|| {
+        paths
+            .par_iter()
+            .enumerate()
+            .map(|(index, path)| {
+                let file_spdx_identifier =
+                    SpdxRef::new(&format!("{}-File-{}", package_spdx_identifier.0, index));
+                let file = FileInformation::analyze(path, file_spdx_identifier, analysis);
+
+                let hashed = hashed.fetch_add(1, Ordering::Relaxed) + 1;
+
+                if hashed % PROGRESS_INTERVAL == 0 {
+                    log::info!(
+                        target: "cargo_spdx",
+                        "hashed {} of {} files of package '{}'",
+                        hashed,
+                        paths.len(),
+                        package.name
+                    );
+                }
+
+                file
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+
+    Ok(files)
+}
+
+/// The types given to files with particular extensions, keyed by lowercase extension.
+pub type FileTypeOverrides = HashMap<String, Vec<FileType>>;
+
+/// Resolve any symlinks and relative components of a path, so paths to the same file compare
+/// equal. Paths which can't be resolved, like ones which don't exist, are kept as they are.
+pub fn canonicalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+/// Detect the types of a file from its extension.
+///
+/// The types in `overrides` for the extension replace the default ones. Files with no
+/// known extension are of type `OTHER`.
+pub fn detect_file_type(path: &Path, overrides: &FileTypeOverrides) -> Vec<FileType> {
+    let extension = match get_extension(path) {
+        Some(extension) => extension,
+        None => return vec![FileType::Other],
+    };
+
+    if let Some(types) = overrides.get(&extension) {
+        return types.clone();
+    }
+
+    match extension.as_str() {
+        "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "s" | "py" | "sh" => vec![FileType::Source],
+        "so" | "dylib" | "dll" | "a" | "lib" | "rlib" | "o" | "exe" | "wasm" => {
+            vec![FileType::Binary]
+        }
+        "md" | "adoc" | "rst" => vec![FileType::Documentation, FileType::Text],
+        "html" => vec![FileType::Documentation],
+        "txt" | "toml" | "lock" | "json" | "yaml" | "yml" => vec![FileType::Text],
+        _ => vec![FileType::Other],
+    }
+}
+
+/// Get the lowercase extension of a file, if it has one.
+fn get_extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+}
+
+/// The type of a file.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, ArgEnum)]
+pub enum FileType {
+    /// Human-readable source code.
+    #[display(fmt = "SOURCE")]
+    Source,
+    /// Compiled object code.
+    #[display(fmt = "BINARY")]
+    Binary,
+    /// Human-readable text.
+    #[display(fmt = "TEXT")]
+    Text,
+    /// Documentation.
+    #[display(fmt = "DOCUMENTATION")]
+    Documentation,
+    /// Anything else.
+    #[display(fmt = "OTHER")]
+    Other,
+}
+
+/// A code identifying the contents of a package whose files were analyzed.
+///
+/// It's the SHA1 of the sorted, concatenated SHA1s of the package's files.
+#[derive(Debug, Clone)]
+pub struct PackageVerificationCode {
+    /// The verification code as a lowercase hexadecimal string.
+    pub value: String,
+    /// The files left out of the verification code.
+    pub excluded_files: Vec<String>,
+}
+
+impl PackageVerificationCode {
+    /// Calculate the verification code from the files of a package.
+    pub fn from_files(files: &[FileInformation]) -> Self {
+        let mut sha1s = files
+            .iter()
+            .filter_map(FileInformation::sha1)
+            .collect::<Vec<_>>();
+        sha1s.sort_unstable();
+
+        let mut hasher = Sha1::new();
+
+        for sha1 in sha1s {
+            hasher.update(sha1.as_bytes());
+        }
+
+        PackageVerificationCode {
+            value: format!("{:x}", hasher.finalize()),
+            excluded_files: vec![],
+        }
+    }
+}
+
+impl Display for PackageVerificationCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)?;
+
+        if self.excluded_files.is_empty().not() {
+            write!(f, " (excludes: {})", self.excluded_files.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{options, package, package_json, TempDir};
+    use rayon::ThreadPoolBuilder;
+
+    /// Write a fixture package with `count` files besides its manifest into `dir`.
+    fn write_fixture(dir: &TempDir, count: usize) -> Package {
+        dir.write("fixture/Cargo.toml", "[package]\nname = \"fixture\"\n");
+
+        for index in 0..count {
+            dir.write(
+                &format!("fixture/src/module_{:03}.rs", index),
+                format!(
+                    "// SPDX-License-Identifier: MIT\npub const N: usize = {};\n",
+                    index
+                ),
+            );
+        }
+
+        package(package_json(dir.path(), "fixture", "0.1.0"))
+    }
+
+    /// Analyze the files of a fixture package on `threads` threads, allowing at most
+    /// `max_files`.
+    fn analyze(
+        dir: &TempDir,
+        package: &Package,
+        threads: usize,
+        max_files: usize,
+    ) -> Result<Vec<FileInformation>> {
+        let target_dir = dir.path().join("target");
+        analyze_with(package, threads, max_files, &target_dir, dir.path())
+    }
+
+    /// Analyze the files of a fixture package, with Cargo's target directory at `target_dir`,
+    /// naming them relative to `strip_prefix`.
+    fn analyze_with(
+        package: &Package,
+        threads: usize,
+        max_files: usize,
+        target_dir: &Path,
+        strip_prefix: &Path,
+    ) -> Result<Vec<FileInformation>> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let overrides = FileTypeOverrides::new();
+        let analysis = FileAnalysis {
+            pool: &pool,
+            target_dir,
+            max_files,
+            algorithms: &[Algorithm::Sha1],
+            overrides: &overrides,
+            strip_prefix,
+        };
+
+        get_package_files(package, &SpdxRef::new("fixture-0.1.0"), &analysis)
+    }
+
+    #[test]
+    fn packages_within_the_file_limit_are_analyzed() {
+        let dir = TempDir::new();
+        let package = write_fixture(&dir, 2);
+
+        let files = analyze(&dir, &package, 1, 3).unwrap();
+        assert_eq!(files.len(), 3);
+
+        // Only the modules have a license tag, the manifest doesn't.
+        let licenses = files
+            .iter()
+            .map(|file| file.license_information_in_file.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(licenses, [vec![], vec!["MIT"], vec!["MIT"]]);
+    }
+
+    #[test]
+    fn packages_over_the_file_limit_fail() {
+        let dir = TempDir::new();
+        let package = write_fixture(&dir, 2);
+
+        let error = analyze(&dir, &package, 1, 2).unwrap_err().to_string();
+        assert!(error.contains("more than 2 files"), "{}", error);
+    }
+
+    #[test]
+    fn parallel_analysis_matches_serial_analysis() {
+        let dir = TempDir::new();
+        let package = write_fixture(&dir, 300);
+
+        let serial = analyze(&dir, &package, 1, 10_000).unwrap();
+        let parallel = analyze(&dir, &package, 8, 10_000).unwrap();
+
+        assert_eq!(serial.len(), 301);
+
+        // Files come out in the order they were walked in, whichever thread hashed them.
+        let names = |files: &[FileInformation]| {
+            files
+                .iter()
+                .map(|file| (file.file_name.clone(), file.file_spdx_identifier.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&serial), names(&parallel));
+
+        let mut sorted = names(&serial);
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(names(&serial), sorted);
+
+        let checksums = |files: &[FileInformation]| {
+            files
+                .iter()
+                .map(|file| file.file_checksum[0].value.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(checksums(&serial), checksums(&parallel));
+    }
+
+    #[test]
+    fn custom_target_dirs_are_skipped() {
+        let dir = TempDir::new();
+        let package = write_fixture(&dir, 1);
+        dir.write("fixture/build-output/debug/fixture", "not a source file");
+        dir.write("fixture/target/debug/fixture", "not a source file");
+
+        let target_dir = dir.path().join("fixture/build-output");
+        let files = analyze_with(&package, 1, 10_000, &target_dir, dir.path()).unwrap();
+
+        // Directories named 'target' are skipped wherever Cargo's target directory is.
+        let names = files
+            .iter()
+            .map(|file| file.file_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["./fixture/Cargo.toml", "./fixture/src/module_000.rs"]
+        );
+    }
+
+    #[test]
+    fn binaries_without_file_names_are_rejected() {
+        for path in ["/", "target/release/.."] {
+            let error = FileInformation::try_from_binary(
+                Path::new(path),
+                SpdxRef::new("fixture-0.1.0-File-0"),
+                &[Algorithm::Sha1],
+                &FileTypeOverrides::new(),
+            )
+            .unwrap_err()
+            .to_string();
+
+            assert_eq!(error, format!("'{}' isn't a file", path));
+        }
+    }
+
+    #[test]
+    fn markdown_is_documentation_and_text() {
+        assert_eq!(
+            detect_file_type(Path::new("README.md"), &FileTypeOverrides::new()),
+            [FileType::Documentation, FileType::Text]
+        );
+    }
+
+    #[test]
+    fn unknown_extensions_are_other() {
+        let overrides = FileTypeOverrides::new();
+
+        assert_eq!(
+            detect_file_type(Path::new("image.xyz"), &overrides),
+            [FileType::Other]
+        );
+        assert_eq!(
+            detect_file_type(Path::new("Makefile"), &overrides),
+            [FileType::Other]
+        );
+    }
+
+    #[test]
+    fn overrides_replace_the_default_types() {
+        let overrides = options(&["--file-type-override", "md=TEXT"]).file_type_overrides();
+
+        assert_eq!(
+            detect_file_type(Path::new("README.MD"), &overrides),
+            [FileType::Text]
+        );
+        assert_eq!(
+            detect_file_type(Path::new("lib.rs"), &overrides),
+            [FileType::Source]
+        );
+    }
+
+    /// Get the names of analyzed files, sorted.
+    fn file_names(files: &[FileInformation]) -> Vec<&str> {
+        let mut names = files
+            .iter()
+            .map(|file| file.file_name.as_str())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn files_are_named_relative_to_the_strip_prefix() {
+        let dir = TempDir::new();
+        let package = write_fixture(&dir, 1);
+        let target_dir = dir.path().join("target");
+
+        let files =
+            analyze_with(&package, 1, 10, &target_dir, &dir.path().join("fixture")).unwrap();
+
+        assert_eq!(file_names(&files), ["./Cargo.toml", "./src/module_000.rs"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_to_the_package_are_resolved_before_stripping() {
+        let dir = TempDir::new();
+        write_fixture(&dir, 1);
+        std::os::unix::fs::symlink(dir.path().join("fixture"), dir.path().join("link")).unwrap();
+
+        // The manifest is reached through the symlink, but the prefix is the real directory.
+        let mut json = package_json(dir.path(), "fixture", "0.1.0");
+        json["manifest_path"] = dir.path().join("link/Cargo.toml").to_str().unwrap().into();
+        let package = package(json);
+        let target_dir = dir.path().join("target");
+
+        let files = analyze_with(&package, 1, 10, &target_dir, dir.path()).unwrap();
+
+        assert_eq!(
+            file_names(&files),
+            ["./fixture/Cargo.toml", "./fixture/src/module_000.rs"]
+        );
+    }
+
+    #[test]
+    fn files_outside_the_strip_prefix_keep_absolute_paths() {
+        let dir = TempDir::new();
+        let package = write_fixture(&dir, 1);
+        let elsewhere = TempDir::new();
+        let target_dir = dir.path().join("target");
+
+        let files = analyze_with(&package, 1, 10, &target_dir, elsewhere.path()).unwrap();
+
+        let expected = [
+            dir.path().join("fixture/Cargo.toml"),
+            dir.path().join("fixture/src/module_000.rs"),
+        ];
+        assert_eq!(
+            file_names(&files),
+            expected
+                .iter()
+                .map(|path| path.to_str().unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! Defines the other licensing information section of the SPDX document.
+
+use anyhow::{anyhow, Result};
+use cargo_metadata::Package;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use spdx::expression::{ExprNode, Operator};
+use spdx::{Expression, ParseMode};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::ops::Not as _;
+use std::path::Path;
+
+/// Matches an `SPDX-License-Identifier` tag, capturing the expression after it.
+static LICENSE_IDENTIFIER: Lazy<Regex> = Lazy::new(|| {
+    // PANIC SAFETY: The pattern is a valid regular expression.
+    Regex::new(r"SPDX-License-Identifier:\s*(.+?)\s*(\*/|-->)?\s*$").unwrap()
+});
+
+/// The prefixes of line comments, and of the lines within block comments, which a file's
+/// header may be made of.
+const COMMENT_PREFIXES: &[&str] = &["//", "#", "/*", "*", "--", "<!--", ";", "%"];
+
+/// A license found in the package which isn't on the SPDX license list.
+#[derive(Debug, Clone)]
+pub struct OtherLicensingInformation {
+    /// The identifier used to refer to the license, of the form `LicenseRef-<idstring>`.
+    pub license_identifier: String,
+
+    /// The full text of the license.
+    pub extracted_text: String,
+
+    /// A freeform comment about the license.
+    pub license_comment: Option<String>,
+}
+
+impl OtherLicensingInformation {
+    /// Read the license file named by the package's `license-file` manifest field.
+    pub fn from_license_file(package: &Package, id_string: &str) -> Result<Self> {
+        let license_file = package
+            .license_file
+            .as_ref()
+            .ok_or_else(|| anyhow!("package '{}' has no license file", package.name))?;
+
+        // The license file is relative to the directory containing the manifest.
+        let path = match package.manifest_path.parent() {
+            Some(dir) => dir.join(license_file),
+            None => license_file.clone(),
+        };
+
+        let extracted_text = fs::read_to_string(&path)
+            .map_err(|e| anyhow!("failed to read license file '{}': {}", path, e))?;
+
+        Ok(OtherLicensingInformation {
+            license_identifier: format!("LicenseRef-{}", id_string),
+            extracted_text,
+            license_comment: Some(format!(
+                "Read from the license file '{}' of package '{}'.",
+                license_file, package.name
+            )),
+        })
+    }
+
+    /// Record a license reference found in a file's `SPDX-License-Identifier` tag.
+    ///
+    /// The text of such licenses is unknown, so it's recorded as making no assertion.
+    pub fn from_identifier_tag(license_identifier: &str) -> Self {
+        OtherLicensingInformation {
+            license_identifier: license_identifier.to_owned(),
+            extracted_text: String::from("NOASSERTION"),
+            license_comment: Some(String::from(
+                "Found in an SPDX-License-Identifier tag, but isn't on the SPDX license list.",
+            )),
+        }
+    }
+}
+
+/// Find the licenses named by the `SPDX-License-Identifier` tags in a file's header.
+///
+/// As with REUSE, only tags in the comments at the top of the file count, so tags quoted in
+/// string literals or patterns further down are ignored. Identifiers on the SPDX license list
+/// are returned as-is, and any others are returned as `LicenseRef-` references. Files which
+/// can't be read as text have no licenses found.
+pub fn scan_license_identifiers(path: &Path) -> Vec<String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return vec![],
+    };
+
+    let mut in_block = false;
+    let mut identifiers = BufReader::new(file)
+        .lines()
+        .map_while(|line| line.ok())
+        .take_while(|line| {
+            let is_header = in_block || is_comment(line);
+            in_block = in_block_comment(line, in_block);
+            is_header
+        })
+        .filter_map(|line| {
+            LICENSE_IDENTIFIER
+                .captures(&line)
+                .and_then(|captures| captures.get(1))
+                .map(|expression| expression.as_str().to_owned())
+        })
+        .flat_map(|expression| license_identifiers(&expression))
+        .collect::<Vec<_>>();
+
+    identifiers.sort();
+    identifiers.dedup();
+    identifiers
+}
+
+/// Check whether a line can be part of a file's header: blank, or starting a comment.
+fn is_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty()
+        || COMMENT_PREFIXES
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+}
+
+/// Check whether a block comment is still open after a line, given whether it was before.
+fn in_block_comment(line: &str, in_block: bool) -> bool {
+    let opened = ["/*", "<!--"]
+        .iter()
+        .filter_map(|open| line.rfind(open))
+        .max();
+    let closed = ["*/", "-->"]
+        .iter()
+        .filter_map(|close| line.rfind(close))
+        .max();
+
+    match (opened, closed) {
+        (Some(opened), Some(closed)) => opened > closed,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => in_block,
+    }
+}
+
+/// Split a license expression into the identifiers of its licenses.
+fn license_identifiers(expression: &str) -> Vec<String> {
+    let mut identifiers = vec![];
+    let mut tokens = expression
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|token| token.is_empty().not());
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "AND" | "OR" | "and" | "or" => {}
+            // Exceptions modify the license before them, and aren't licenses themselves.
+            "WITH" | "with" => {
+                tokens.next();
+            }
+            token if token.starts_with("LicenseRef-") => identifiers.push(token.to_owned()),
+            token if spdx::license_id(token.trim_end_matches('+')).is_some() => {
+                identifiers.push(token.to_owned())
+            }
+            token => identifiers.push(format!(
+                "LicenseRef-{}",
+                token.replace(|c: char| c.is_ascii_alphanumeric().not() && c != '.', "-")
+            )),
+        }
+    }
+
+    identifiers
+}
+
+/// Rewrite a license expression with the official SPDX identifiers of its licenses.
+///
+/// Imprecise license names like `Apache2` and lowercase operators are accepted, and the
+/// deprecated `/` separator is read as `OR`. Returns `None` if the expression can't be parsed
+/// even then.
+pub fn normalize_expression(expression: &str) -> Option<String> {
+    let expression = Expression::parse_mode(expression, ParseMode::Lax).ok()?;
+
+    // The expression is iterated in postfix order, so each operator joins the two operands
+    // before it. Operands joined by `OR` are parenthesized within an `AND`, which binds tighter.
+    let mut operands: Vec<(String, Option<Operator>)> = vec![];
+
+    for node in expression.iter() {
+        match node {
+            ExprNode::Req(req) => operands.push((req.req.to_string(), None)),
+            ExprNode::Op(op) => {
+                let (right, right_op) = operands.pop()?;
+                let (left, left_op) = operands.pop()?;
+
+                let wrap = |operand: String, operand_op: Option<Operator>| {
+                    if *op == Operator::And && operand_op == Some(Operator::Or) {
+                        format!("({})", operand)
+                    } else {
+                        operand
+                    }
+                };
+
+                let keyword = match op {
+                    Operator::And => "AND",
+                    Operator::Or => "OR",
+                };
+
+                operands.push((
+                    format!(
+                        "{} {} {}",
+                        wrap(left, left_op),
+                        keyword,
+                        wrap(right, right_op)
+                    ),
+                    Some(*op),
+                ));
+            }
+        }
+    }
+
+    match (operands.pop(), operands.is_empty()) {
+        (Some((normalized, _)), true) => Some(normalized),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// Scan a file with the given contents for license identifiers.
+    fn scan(contents: &str) -> Vec<String> {
+        let dir = TempDir::new();
+        let path = dir.write("file", contents);
+        scan_license_identifiers(&path)
+    }
+
+    #[test]
+    fn line_comment_tags_are_found() {
+        assert_eq!(
+            scan("// SPDX-License-Identifier: MIT\nfn main() {}\n"),
+            ["MIT"]
+        );
+        assert_eq!(
+            scan("#!/bin/sh\n# SPDX-License-Identifier: Apache-2.0\necho\n"),
+            ["Apache-2.0"]
+        );
+    }
+
+    #[test]
+    fn block_comment_trailers_are_dropped() {
+        assert_eq!(
+            scan("/* SPDX-License-Identifier: MIT */\nint x;\n"),
+            ["MIT"]
+        );
+        assert_eq!(
+            scan("<!-- SPDX-License-Identifier: BSD-3-Clause -->\n<p></p>\n"),
+            ["BSD-3-Clause"]
+        );
+    }
+
+    #[test]
+    fn tags_within_block_comments_are_found() {
+        assert_eq!(
+            scan("/*\n  Copyright\n\n  SPDX-License-Identifier: MIT\n*/\nint x;\n"),
+            ["MIT"]
+        );
+    }
+
+    #[test]
+    fn tags_after_the_header_are_ignored() {
+        assert_eq!(
+            scan(
+                "//! A module.\n\nuse regex::Regex;\n\
+                 // SPDX-License-Identifier: GPL-3.0-only\n\
+                 const TAG: &str = \"SPDX-License-Identifier: GPL-3.0-only\";\n"
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn exceptions_are_dropped() {
+        assert_eq!(
+            scan("// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception\n"),
+            ["Apache-2.0"]
+        );
+    }
+
+    #[test]
+    fn unknown_identifiers_are_references() {
+        assert_eq!(
+            scan("# SPDX-License-Identifier: My_License OR LicenseRef-Other\n"),
+            ["LicenseRef-My-License", "LicenseRef-Other"]
+        );
+    }
+
+    #[test]
+    fn identifiers_are_deduplicated() {
+        assert_eq!(
+            scan(
+                "// SPDX-License-Identifier: MIT OR Apache-2.0\n\
+                 // SPDX-License-Identifier: MIT\n"
+            ),
+            ["Apache-2.0", "MIT"]
+        );
+    }
+
+    #[test]
+    fn slashes_are_read_as_or() {
+        assert_eq!(
+            normalize_expression("MIT/Apache-2.0").as_deref(),
+            Some("MIT OR Apache-2.0")
+        );
+    }
+
+    #[test]
+    fn imprecise_names_and_lowercase_operators_are_normalized() {
+        assert_eq!(
+            normalize_expression("apache2 and mit").as_deref(),
+            Some("Apache-2.0 AND MIT")
+        );
+    }
+
+    #[test]
+    fn alternatives_within_a_conjunction_keep_their_parentheses() {
+        assert_eq!(
+            normalize_expression("(MIT OR Apache-2.0) AND BSD-3-Clause").as_deref(),
+            Some("(MIT OR Apache-2.0) AND BSD-3-Clause")
+        );
+    }
+
+    #[test]
+    fn conjunctions_within_alternatives_need_no_parentheses() {
+        assert_eq!(
+            normalize_expression("MIT AND Apache-2.0 OR BSD-3-Clause").as_deref(),
+            Some("MIT AND Apache-2.0 OR BSD-3-Clause")
+        );
+    }
+
+    #[test]
+    fn exceptions_are_kept() {
+        assert_eq!(
+            normalize_expression("Apache-2.0 WITH LLVM-exception OR MIT").as_deref(),
+            Some("Apache-2.0 WITH LLVM-exception OR MIT")
+        );
+    }
+
+    #[test]
+    fn unparseable_expressions_are_none() {
+        assert_eq!(normalize_expression("MIT OR"), None);
+        assert_eq!(normalize_expression("AND MIT"), None);
+    }
+}
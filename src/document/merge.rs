@@ -0,0 +1,302 @@
+//! Carries manual conclusions forward from a previously generated SPDX document.
+
+use crate::document::package::{is_generated_comment, LicenseExpression, SpdxValue};
+use crate::document::PackageInformation;
+use crate::format::Format;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Not as _;
+use std::path::Path;
+
+/// The fields of a package in a prior document which may have been set by a reviewer.
+#[derive(Debug, Default)]
+struct PriorPackage {
+    /// The concluded license.
+    concluded_license: Option<String>,
+    /// The copyright text.
+    copyright_text: Option<String>,
+    /// The comments on the license.
+    comments_on_license: Option<String>,
+}
+
+/// Carry conclusions forward from the document at `path` into the freshly built packages.
+///
+/// Packages are matched on their identifiers. For each match, any concluded license or
+/// copyright text the prior document asserts replace the freshly built ones, as do license
+/// comments written by a reviewer. Generated license comments are written afresh instead.
+/// Packages only in the prior document are dropped, and packages only in the new one are left
+/// as-is.
+pub fn merge_existing(path: &Path, packages: &mut [PackageInformation]) -> Result<()> {
+    if Format::from_path(path) != Some(Format::KeyValue) {
+        return Err(anyhow!(
+            "only {} documents can be merged, but '{}' isn't one",
+            Format::KeyValue,
+            path.display()
+        ));
+    }
+
+    log::info!(target: "cargo_spdx", "merging the existing document '{}'", path.display());
+
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read '{}': {}", path.display(), e))?;
+    let mut prior_packages = read_packages(&contents);
+
+    for package in packages {
+        let prior = match prior_packages.remove(&package.package_spdx_identifier.to_string()) {
+            Some(prior) => prior,
+            None => continue,
+        };
+
+        if let Some(concluded_license) = prior.concluded_license {
+            package.concluded_license = SpdxValue::Value(LicenseExpression(concluded_license));
+        }
+
+        if let Some(copyright_text) = prior.copyright_text {
+            package.copyright_text = SpdxValue::Value(copyright_text);
+        }
+
+        if let Some(comments_on_license) = prior.comments_on_license {
+            if is_generated_comment(&comments_on_license).not() {
+                package.comments_on_license = Some(comments_on_license);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the packages of a key-value document, keyed on their identifiers.
+fn read_packages(contents: &str) -> HashMap<String, PriorPackage> {
+    let mut packages = HashMap::new();
+    let mut current: Option<(Option<String>, PriorPackage)> = None;
+
+    for (tag, value) in read_tag_values(contents) {
+        match tag.as_str() {
+            // A package's fields run until the next package, or the start of another section.
+            "PackageName" | "FileName" | "LicenseID" | "Annotator" | "Relationship" => {
+                if let Some((Some(id), package)) = current.take() {
+                    packages.insert(id, package);
+                }
+
+                if tag == "PackageName" {
+                    current = Some((None, PriorPackage::default()));
+                }
+            }
+            _ => {
+                let (id, package) = match &mut current {
+                    Some(current) => current,
+                    None => continue,
+                };
+
+                // Fields which make no assertion have nothing to carry forward.
+                let asserted = (value != "NOASSERTION").then(|| value);
+
+                match tag.as_str() {
+                    "SPDXID" => *id = asserted,
+                    "PackageLicenseConcluded" => package.concluded_license = asserted,
+                    "PackageCopyrightText" => package.copyright_text = asserted,
+                    "PackageLicenseComments" => package.comments_on_license = asserted,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    if let Some((Some(id), package)) = current {
+        packages.insert(id, package);
+    }
+
+    packages
+}
+
+/// Split a key-value document into its tags and values.
+///
+/// Values wrapped in `<text>` tags may span several lines, and are unwrapped.
+pub fn read_tag_values(contents: &str) -> Vec<(String, String)> {
+    let mut tag_values = vec![];
+    let mut lines = contents.lines();
+
+    while let Some(line) = lines.next() {
+        let (tag, value) = match line.split_once(':') {
+            Some((tag, value)) if line.starts_with('#').not() => (tag.trim(), value.trim()),
+            _ => continue,
+        };
+
+        let value = match value.strip_prefix("<text>") {
+            Some(text) => {
+                let mut text = text.to_owned();
+
+                while text.contains("</text>").not() {
+                    match lines.next() {
+                        Some(line) => {
+                            text.push('\n');
+                            text.push_str(line);
+                        }
+                        None => break,
+                    }
+                }
+
+                text.split("</text>").next().unwrap_or_default().to_owned()
+            }
+            None => value.to_owned(),
+        };
+
+        tag_values.push((tag.to_owned(), value));
+    }
+
+    tag_values
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::document::package::{LicenseExpression, SpdxValue, GENERATED_COMMENT_PREFIX};
+    use crate::document::Document;
+    use crate::format;
+    use crate::test_support::{MetadataFixture, TempDir};
+
+    #[test]
+    fn reviewer_conclusions_survive_regeneration() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let removed = fixture.package("removed", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &removed);
+
+        // A reviewer concludes the license of the root package, and comments on it.
+        let mut output = Vec::new();
+        format::key_value::write(&mut output, &fixture.build(&[])).unwrap();
+        let reviewed = String::from_utf8(output)
+            .unwrap()
+            .replacen(
+                "PackageLicenseConcluded: NOASSERTION",
+                "PackageLicenseConcluded: MIT",
+                1,
+            )
+            .replacen(
+                "PackageLicenseComments: <text>Generated by cargo-spdx: The crate manifest has no \
+                 license field.</text>",
+                "PackageLicenseComments: <text>Checked by\nlegal.</text>",
+                1,
+            );
+        let path = dir.write("reviewed.spdx", reviewed);
+
+        // The dependencies change before the document is regenerated.
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let added = fixture.package("added", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &added);
+
+        let doc = fixture.build(&["--merge-existing", path.to_str().unwrap()]);
+
+        let names = doc
+            .package_information
+            .iter()
+            .map(|package| package.package_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["fixture", "added"]);
+
+        let root = &doc.package_information[0];
+        assert_eq!(
+            root.concluded_license,
+            SpdxValue::Value(LicenseExpression(String::from("MIT")))
+        );
+        assert_eq!(
+            root.comments_on_license.as_deref(),
+            Some("Checked by\nlegal.")
+        );
+        assert_eq!(
+            doc.package_information[1].concluded_license,
+            SpdxValue::NoAssertion
+        );
+    }
+
+    /// A fixture with a single package, declaring the MIT license.
+    fn licensed_fixture(dir: &TempDir) -> MetadataFixture {
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package_with("fixture", "0.1.0", |json| {
+            json["license"] = serde_json::json!("MIT");
+        });
+        fixture.member(&root);
+        fixture
+    }
+
+    /// Write a document out in the key-value format.
+    fn key_value(doc: &Document) -> String {
+        let mut output = Vec::new();
+        format::key_value::write(&mut output, doc).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn generated_comments_round_trip_unchanged() {
+        let dir = TempDir::new();
+        let fixture = licensed_fixture(&dir);
+
+        let first = fixture.build(&[]);
+        let path = dir.write("first.spdx", key_value(&first));
+        let second = fixture.build(&["--merge-existing", path.to_str().unwrap()]);
+
+        let comment = first.package_information[0].comments_on_license.as_deref();
+        assert!(comment.unwrap().starts_with(GENERATED_COMMENT_PREFIX));
+        assert_eq!(
+            second.package_information[0].comments_on_license.as_deref(),
+            comment
+        );
+        assert_eq!(
+            second.package_information[0].concluded_license,
+            SpdxValue::NoAssertion
+        );
+    }
+
+    #[test]
+    fn generated_comments_are_not_carried_past_a_conclusion() {
+        let dir = TempDir::new();
+        let fixture = licensed_fixture(&dir);
+
+        // The reviewer concludes a license, leaving the generated comment as it was.
+        let reviewed = key_value(&fixture.build(&[])).replacen(
+            "PackageLicenseConcluded: NOASSERTION",
+            "PackageLicenseConcluded: MIT",
+            1,
+        );
+        assert!(reviewed.contains("No license was concluded"));
+        let path = dir.write("reviewed.spdx", reviewed);
+
+        let doc = fixture.build(&["--merge-existing", path.to_str().unwrap()]);
+        let root = &doc.package_information[0];
+
+        assert_eq!(
+            root.concluded_license,
+            SpdxValue::Value(LicenseExpression(String::from("MIT")))
+        );
+        assert_eq!(root.comments_on_license, None);
+    }
+
+    #[test]
+    fn reviewer_comments_are_left_alone() {
+        let dir = TempDir::new();
+        let fixture = licensed_fixture(&dir);
+
+        // The reviewer explains why they haven't concluded a license yet.
+        let comment = "PackageLicenseComments: <text>Generated by cargo-spdx: No license was \
+                       concluded, since the package wasn't reviewed beyond its manifest.</text>";
+        let output = key_value(&fixture.build(&[]));
+        assert!(output.contains(comment), "{}", output);
+        let reviewed = output.replacen(
+            comment,
+            "PackageLicenseComments: <text>Waiting on legal.</text>",
+            1,
+        );
+        let path = dir.write("reviewed.spdx", reviewed);
+
+        let doc = fixture.build(&["--merge-existing", path.to_str().unwrap()]);
+
+        assert_eq!(
+            doc.package_information[0].comments_on_license.as_deref(),
+            Some("Waiting on legal.")
+        );
+    }
+}
@@ -0,0 +1,1683 @@
+//! Defines the SPDX document structure.
+
+use crate::advisory::AdvisoryDatabase;
+use crate::cargo::{CrateMetadata, LockChecksums};
+use crate::git::{get_current_user, get_file_authors, get_origin};
+use crate::registry::RegistryIndex;
+use crate::{ErrorKind, GenerateOptions};
+use anyhow::{anyhow, Context as _, Error, Result};
+use cargo_metadata::Package;
+use derive_builder::Builder;
+use derive_more::{Display, From};
+use rayon::ThreadPoolBuilder;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::ops::Not as _;
+use std::str::FromStr;
+use time::{format_description, OffsetDateTime};
+use url::Url;
+use uuid::Uuid;
+
+pub use annotation::{Annotation, AnnotationType};
+pub use binary::build_from_binary;
+pub use checksum::{Algorithm, Checksum};
+pub use existing::read_package_versions;
+pub use file::{FileInformation, FileType, FileTypeOverrides};
+pub use license::OtherLicensingInformation;
+pub use package::{ExternalReference, PackageInformation, PackageRefs, SpdxRef, SpdxValue};
+pub use relationship::{Relationship, RelationshipType};
+
+use checksum::calculate_checksums;
+use file::FileAnalysis;
+use package::PurlPrefix;
+use policy::LicensePolicy;
+
+mod annotation;
+mod binary;
+mod checksum;
+mod existing;
+mod file;
+mod license;
+mod merge;
+mod package;
+mod policy;
+mod relationship;
+mod split;
+
+/// The name and version of this tool, as it's recorded in the document.
+const TOOL: &str = concat!("cargo-spdx ", env!("CARGO_PKG_VERSION"));
+
+/// Build a new SPDX document based on collected information.
+pub fn build(
+    args: &GenerateOptions,
+    output_file_name: &str,
+    metadata: &CrateMetadata,
+) -> Result<Document> {
+    log::info!(target: "cargo_spdx", "building the document");
+
+    let refs = PackageRefs::new(metadata);
+    let index = (args.check_yanked() || args.strip_path_dependencies())
+        .then(|| RegistryIndex::fetch(args))
+        .flatten();
+
+    let mut other_licenses = Vec::new();
+    let mut invalid_licenses = 0;
+    let mut packages = get_package_information(
+        args,
+        metadata,
+        &refs,
+        index.as_ref(),
+        &mut other_licenses,
+        &mut invalid_licenses,
+    )?;
+
+    if invalid_licenses > 0 {
+        log::warn!(
+            target: "cargo_spdx",
+            "{} packages have license expressions which couldn't be parsed, so their declared \
+             licenses are NOASSERTION",
+            invalid_licenses
+        );
+    }
+
+    // Conclusions made by reviewers in a prior document survive regenerating it.
+    if let Some(path) = args.merge_existing() {
+        merge::merge_existing(path, &mut packages)?;
+    }
+
+    for package in &mut packages {
+        package.note_unconcluded_license();
+        package.sort_external_references();
+    }
+
+    let mut relationships = get_relationships(args, metadata, &refs, &packages)?;
+    let created = get_created(args)?;
+    let annotations = get_annotations(args, metadata, &refs, index.as_ref(), &created);
+
+    // Reproducible output can't depend on the order packages were discovered in.
+    if args.reproducible() {
+        packages.sort_by(|a, b| a.package_spdx_identifier.cmp(&b.package_spdx_identifier));
+        relationships.sort();
+    }
+
+    // Construct the document.
+    Ok(DocumentBuilder::default()
+        .spdx_version(args.spdx_version())
+        .document_name(output_file_name)
+        .document_namespace(get_document_namespace(args, output_file_name, &packages)?)
+        .creator(get_creator(args)?)
+        .created(created)
+        .external_document_references(args.external_document_references().to_vec())
+        .license_list_version(LICENSE_LIST_VERSION)
+        .creator_comment(args.creator_comment().map(CreatorComment::from))
+        .document_comment(get_document_comment(args, metadata))
+        .package_information(packages)
+        .other_licensing_information_detected(other_licenses)
+        .relationships(relationships)
+        .annotations(annotations)
+        .build()?)
+}
+
+/// Get the namespace of the document.
+///
+/// Unless the user pins a namespace, one is made unique by appending the document name
+/// and a UUID to the host URL. The UUID is random, except when building reproducibly,
+/// where it's derived from the packages in the document. Without a host URL, the namespace
+/// is derived from the git origin remote and current commit if there is one, like
+/// `https://github.com/org/repo/sbom/<commit>-<uuid>`.
+pub fn get_document_namespace(
+    args: &GenerateOptions,
+    document_name: &str,
+    packages: &[PackageInformation],
+) -> Result<DocumentNamespace> {
+    if let Some(namespace) = args.namespace() {
+        return DocumentNamespace::try_from(namespace);
+    }
+
+    let uuid = if args.reproducible() {
+        let identifiers = packages
+            .iter()
+            .map(|package| package.package_spdx_identifier.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Uuid::new_v5(&Uuid::NAMESPACE_URL, identifiers.as_bytes())
+    } else {
+        Uuid::new_v4()
+    };
+
+    if args.has_host_url().not() {
+        match get_origin() {
+            Ok(origin) => {
+                let namespace = format!("{}/sbom/{}-{}", origin.url, origin.commit, uuid);
+                return DocumentNamespace::try_from(namespace.as_str());
+            }
+            Err(e) => log::info!(
+                target: "cargo_spdx",
+                "can't derive the namespace from git, so a host URL is needed: {}",
+                e
+            ),
+        }
+    }
+
+    let host_url = args.host_url()?;
+    let namespace = format!(
+        "{}/{}-{}",
+        host_url.trim_end_matches('/'),
+        document_name,
+        uuid
+    );
+
+    DocumentNamespace::try_from(namespace.as_str())
+}
+
+/// Get the comment on the document.
+///
+/// This is the comment given by the user, followed by the version of the feature resolver and
+/// the command line which generated the document if they're to be recorded.
+pub fn get_document_comment(
+    args: &GenerateOptions,
+    metadata: &CrateMetadata,
+) -> Option<DocumentComment> {
+    let resolver = args
+        .comment_with_resolver()
+        .then(|| metadata.resolver())
+        .flatten()
+        .map(|resolver| format!("Cargo feature resolver v{}.", resolver));
+
+    let invocation = args.record_invocation().then(|| {
+        format!(
+            "Generated with: {}",
+            get_invocation(
+                env::args_os().map(|arg| arg.to_string_lossy().into_owned()),
+                args.redacted_words()
+            )
+        )
+    });
+
+    let comment = args
+        .document_comment()
+        .map(ToOwned::to_owned)
+        .into_iter()
+        .chain(resolver)
+        .chain(invocation)
+        .collect::<Vec<_>>();
+
+    comment
+        .is_empty()
+        .not()
+        .then(|| DocumentComment(comment.join(" ")))
+}
+
+/// Words which mark an argument as a secret whose value is redacted from the command line.
+const REDACTED_WORDS: &[&str] = &["token", "password", "secret", "key"];
+
+/// Reconstruct a command line from its arguments, redacting secrets.
+///
+/// The value of any option or `NAME=value` pair whose name contains one of `REDACTED_WORDS` or
+/// `extra_words`, ignoring case, is replaced by `<redacted>`. Arguments with whitespace or
+/// quotes are quoted.
+fn get_invocation(command_line: impl Iterator<Item = String>, extra_words: &[String]) -> String {
+    let is_secret = |name: &str| {
+        let name = name.to_lowercase();
+
+        REDACTED_WORDS
+            .iter()
+            .copied()
+            .chain(extra_words.iter().map(String::as_str))
+            .any(|word| name.contains(&word.to_lowercase()))
+    };
+
+    let mut redact_next = false;
+    let mut parts = vec![];
+
+    for arg in command_line {
+        let part = if redact_next {
+            redact_next = false;
+            String::from("<redacted>")
+        } else {
+            match arg.split_once('=') {
+                Some((name, _)) if is_secret(name) => format!("{}=<redacted>", name),
+                None if arg.starts_with('-') && is_secret(&arg) => {
+                    redact_next = true;
+                    arg
+                }
+                _ => arg,
+            }
+        };
+
+        if part.contains(|c: char| c.is_whitespace() || c == '\'' || c == '"') {
+            parts.push(format!("'{}'", part.replace('\'', r"'\''")));
+        } else {
+            parts.push(part);
+        }
+    }
+
+    parts.join(" ")
+}
+
+/// Get the time the document was created.
+///
+/// `SOURCE_DATE_EPOCH` is honored if it's set. Otherwise it's the current time, or the
+/// Unix epoch when building reproducibly.
+pub fn get_created(args: &GenerateOptions) -> Result<Created> {
+    match env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => parse_source_date_epoch(&epoch).context(ErrorKind::Usage),
+        Err(_) if args.reproducible() => Ok(Created(OffsetDateTime::UNIX_EPOCH)),
+        Err(_) => Ok(Created::default()),
+    }
+}
+
+/// Parse the number of seconds since the Unix epoch `SOURCE_DATE_EPOCH` is set to.
+fn parse_source_date_epoch(epoch: &str) -> Result<Created> {
+    epoch
+        .trim()
+        .parse::<i64>()
+        .map_err(Error::from)
+        .and_then(|seconds| Ok(OffsetDateTime::from_unix_timestamp(seconds)?))
+        .map(Created)
+        .with_context(|| format!("invalid SOURCE_DATE_EPOCH '{}'", epoch))
+}
+
+/// Identify the creator(s) of the SBOM.
+///
+/// A creator given by the user replaces the person detected from the Git configuration.
+pub fn get_creator(args: &GenerateOptions) -> Result<Vec<Creator>> {
+    let mut creator = vec![];
+
+    match args.creator() {
+        Some(person) => creator.push(
+            Creator::parse_person(person)
+                .ok_or_else(|| anyhow!("--creator must be of the form 'Name <email>'"))?,
+        ),
+        None => {
+            if let Ok(user) = get_current_user() {
+                creator.push(Creator::person(user.name, user.email));
+            }
+        }
+    }
+
+    creator.push(Creator::tool(TOOL));
+    Ok(creator)
+}
+
+/// Collect the annotations on packages in the document.
+///
+/// When analyzing files, packages with a build script are annotated, since the script runs
+/// at build time and may generate sources which aren't among the package's files. Packages
+/// of crates present in more than one version are annotated with the coexisting versions, and
+/// every package is annotated with its edition and minimum Rust version. Proc-macro packages
+/// are annotated, since they run arbitrary code at compile time.
+/// Packages whose version was yanked from crates.io are annotated for review if requested.
+pub fn get_annotations(
+    args: &GenerateOptions,
+    metadata: &CrateMetadata,
+    refs: &PackageRefs,
+    index: Option<&RegistryIndex>,
+    created: &Created,
+) -> Vec<Annotation> {
+    let mut annotations = vec![];
+
+    if args.with_files() {
+        annotations.extend(
+            metadata
+                .packages()
+                .filter(|package| has_target_kind(package, "custom-build"))
+                .map(|package| {
+                    Annotation::new(
+                        created,
+                        AnnotationType::Other,
+                        refs.get(package),
+                        String::from(
+                            "The package has a build script, which runs at build time and may \
+                             generate sources that aren't among the package's files.",
+                        ),
+                    )
+                }),
+        );
+    }
+
+    annotations.extend(
+        metadata
+            .packages()
+            .filter(|package| is_proc_macro(package))
+            .map(|package| {
+                Annotation::new(
+                    created,
+                    AnnotationType::Other,
+                    refs.get(package),
+                    String::from("proc-macro: executes at compile time"),
+                )
+            }),
+    );
+
+    if metadata.truncated() {
+        annotations.push(Annotation::new(
+            created,
+            AnnotationType::Other,
+            SpdxRef::document(),
+            format!(
+                "Dependencies more than {} edges from the root package were omitted.",
+                args.max_depth().unwrap_or_default()
+            ),
+        ));
+    }
+
+    if args.annotate_lock_hash() {
+        annotations.extend(get_lock_hash_annotation(metadata, created));
+    }
+
+    annotations.extend(get_duplicate_version_annotations(metadata, refs, created));
+    annotations.extend(get_toolchain_annotations(metadata, refs, created));
+
+    if let Some(index) = index.filter(|_| args.check_yanked()) {
+        annotations.extend(
+            metadata
+                .packages()
+                .filter(|package| index.is_yanked(package))
+                .map(|package| {
+                    log::warn!(
+                        target: "cargo_spdx",
+                        "version {} of package '{}' was yanked from crates.io",
+                        package.version,
+                        package.name
+                    );
+
+                    Annotation::new(
+                        created,
+                        AnnotationType::Review,
+                        refs.get(package),
+                        String::from("Version yanked from registry."),
+                    )
+                }),
+        );
+    }
+
+    annotations
+}
+
+/// Get the annotation of the document with the SHA-256 of the workspace's `Cargo.lock`.
+///
+/// A warning is logged if there's no lockfile to hash.
+fn get_lock_hash_annotation(metadata: &CrateMetadata, created: &Created) -> Option<Annotation> {
+    let path = metadata.workspace_root().join("Cargo.lock");
+
+    match calculate_checksums(&path, &[Algorithm::Sha256]) {
+        Ok(checksums) => checksums.into_iter().next().map(|checksum| {
+            Annotation::new(
+                created,
+                AnnotationType::Other,
+                SpdxRef::document(),
+                format!("cargo-lock-sha256: {}", checksum.value),
+            )
+        }),
+        Err(e) => {
+            log::warn!(
+                target: "cargo_spdx",
+                "not annotating the lockfile hash, as '{}' couldn't be read: {}",
+                path.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Check whether a package has a target of the given kind, like "custom-build".
+fn has_target_kind(package: &Package, kind: &str) -> bool {
+    package
+        .targets
+        .iter()
+        .any(|target| target.kind.iter().any(|k| k == kind))
+}
+
+/// Check whether a package is a proc-macro crate.
+fn is_proc_macro(package: &Package) -> bool {
+    has_target_kind(package, "proc-macro")
+}
+
+/// Annotate the packages with the edition and minimum Rust version they're built with.
+///
+/// Annotations are formatted like `edition=2021; rust-version=1.70`, leaving out whatever the
+/// package doesn't specify.
+fn get_toolchain_annotations(
+    metadata: &CrateMetadata,
+    refs: &PackageRefs,
+    created: &Created,
+) -> Vec<Annotation> {
+    metadata
+        .packages()
+        .filter_map(|package| {
+            let mut fields = vec![];
+
+            if package.edition.is_empty().not() {
+                fields.push(format!("edition={}", package.edition));
+            }
+
+            // Cargo reads the minimum Rust version as a requirement, like '^1.70'.
+            if let Some(rust_version) = &package.rust_version {
+                let rust_version = rust_version.to_string();
+                fields.push(format!(
+                    "rust-version={}",
+                    rust_version.trim_start_matches('^')
+                ));
+            }
+
+            fields.is_empty().not().then(|| {
+                Annotation::new(
+                    created,
+                    AnnotationType::Other,
+                    refs.get(package),
+                    fields.join("; "),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Annotate the packages for crates which are present in more than one version, counting
+/// the same version from different sources as different versions.
+fn get_duplicate_version_annotations(
+    metadata: &CrateMetadata,
+    refs: &PackageRefs,
+    created: &Created,
+) -> Vec<Annotation> {
+    let mut versions = BTreeMap::<&str, Vec<&Package>>::new();
+
+    for package in metadata.packages() {
+        versions.entry(&package.name).or_default().push(package);
+    }
+
+    let source_of = |package: &Package| package.source.as_ref().map(|source| source.repr.clone());
+
+    versions.retain(|_, packages| {
+        packages.sort_by_key(|package| (package.version.clone(), source_of(package)));
+        packages.dedup_by_key(|package| (package.version.clone(), source_of(package)));
+        packages.len() > 1
+    });
+
+    if versions.is_empty().not() {
+        log::info!(
+            target: "cargo_spdx",
+            "found {} crates present in more than one version",
+            versions.len()
+        );
+    }
+
+    versions
+        .into_iter()
+        .flat_map(|(name, packages)| {
+            // Versions present from more than one source are told apart by their sources.
+            let shared_version = packages
+                .windows(2)
+                .any(|pair| pair[0].version == pair[1].version);
+
+            let coexisting = packages
+                .iter()
+                .map(|package| match &package.source {
+                    Some(source) if shared_version => format!("{} ({})", package.version, source),
+                    None if shared_version => format!("{} (path)", package.version),
+                    _ => package.version.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            packages.into_iter().map(move |package| {
+                Annotation::new(
+                    created,
+                    AnnotationType::Other,
+                    refs.get(package),
+                    format!(
+                        "Multiple versions of '{}' are present: {}.",
+                        name, coexisting
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// How many packages are processed between each progress message.
+const PROGRESS_INTERVAL: usize = 100;
+
+/// Collect the information for every package in the resolved dependency graph.
+///
+/// The described packages always come first, and each package appears exactly once, keyed
+/// on its package ID, which is unique to its name, version, and source.
+/// The files of the described packages are analyzed if requested, and the build profile is
+/// recorded in their comments if one was given.
+/// Any licenses found which aren't on the SPDX license list are added to `other_licenses`,
+/// and packages whose license expressions couldn't be parsed are counted in
+/// `invalid_licenses`. Path dependencies found in the crates.io `index` are recorded as their crates.io packages
+/// if requested.
+pub fn get_package_information(
+    args: &GenerateOptions,
+    metadata: &CrateMetadata,
+    refs: &PackageRefs,
+    index: Option<&RegistryIndex>,
+    other_licenses: &mut Vec<OtherLicensingInformation>,
+    invalid_licenses: &mut usize,
+) -> Result<Vec<PackageInformation>> {
+    let described = metadata.described()?;
+    let mut seen = HashSet::new();
+
+    let advisories = args
+        .with_advisories()
+        .then(AdvisoryDatabase::fetch)
+        .flatten();
+
+    // Files are analyzed on a pool of worker threads.
+    let pool = args
+        .with_files()
+        .then(|| ThreadPoolBuilder::new().num_threads(args.jobs()).build())
+        .transpose()?;
+
+    let algorithms = args.checksum_algorithms();
+    let lock_checksums = LockChecksums::read(metadata);
+    let purl_prefix = PurlPrefix::from_args(args);
+    let file_type_overrides = args.file_type_overrides();
+    let strip_prefix = file::canonicalize(
+        args.strip_prefix()
+            .unwrap_or_else(|| metadata.workspace_root()),
+    );
+    let analysis = pool.as_ref().map(|pool| FileAnalysis {
+        pool,
+        target_dir: metadata.target_directory(),
+        max_files: args.max_files(),
+        algorithms: &algorithms,
+        overrides: &file_type_overrides,
+        strip_prefix: &strip_prefix,
+    });
+
+    // The history is walked once for the files of every package.
+    let file_authors = (pool.is_some() && args.file_contributors())
+        .then(|| match get_file_authors(metadata.workspace_root()) {
+            Ok(file_authors) => Some(file_authors),
+            Err(e) => {
+                log::warn!(
+                    target: "cargo_spdx",
+                    "can't read git history, so file contributors are taken from AUTHORS \
+                     files: {}",
+                    e
+                );
+                None
+            }
+        })
+        .flatten();
+
+    if pool.is_some() && algorithms.contains(&Algorithm::Md5) {
+        log::warn!(
+            target: "cargo_spdx",
+            "MD5 is deprecated and only supported for legacy verification systems"
+        );
+    }
+
+    let described_ids = described
+        .iter()
+        .map(|package| &package.id)
+        .collect::<HashSet<_>>();
+
+    let packages = described
+        .iter()
+        .copied()
+        .chain(metadata.packages())
+        .filter(|package| seen.insert(&package.id))
+        .enumerate()
+        .map(|(index, package)| -> Result<PackageInformation> {
+            if (index + 1) % PROGRESS_INTERVAL == 0 {
+                log::info!(target: "cargo_spdx", "processed {} packages", index + 1);
+            }
+
+            let mut info = PackageInformation::from_metadata_package(
+                package,
+                refs.get(package),
+                metadata.workspace_root(),
+                other_licenses,
+                args.conclude_declared(),
+                args.normalize_licenses(),
+                purl_prefix,
+            );
+
+            if package.license.is_some() && info.declared_license == SpdxValue::NoAssertion {
+                *invalid_licenses += 1;
+            }
+
+            if let Some(lock_checksums) = &lock_checksums {
+                info.add_lock_checksum(package, lock_checksums);
+            }
+
+            // Path dependencies are rewritten to their crates.io versions when published.
+            if let Some(index) = index.filter(|_| args.strip_path_dependencies()) {
+                if package.source.is_none() && described_ids.contains(&package.id).not() {
+                    if index.is_published(package) {
+                        info.publish_from_crates_io(package, purl_prefix);
+                    } else {
+                        log::warn!(
+                            target: "cargo_spdx",
+                            "version {} of path dependency '{}' isn't on crates.io, so it's \
+                             left as a path dependency",
+                            package.version,
+                            package.name
+                        );
+                    }
+                }
+            }
+
+            if let Some(advisories) = &advisories {
+                info.external_reference
+                    .extend(advisories.references(package));
+            }
+
+            if let Some(profile) = args.profile() {
+                if described_ids.contains(&package.id) {
+                    info.package_comment = Some(get_profile_comment(metadata, profile));
+                }
+            }
+
+            if let Some(analysis) = &analysis {
+                if described_ids.contains(&package.id) {
+                    info.analyze_files(package, analysis)?;
+
+                    if args.file_contributors() {
+                        info.add_file_contributors(package, &strip_prefix, file_authors.as_ref());
+                    }
+
+                    // Licenses named in files which aren't on the SPDX license list are
+                    // recorded once each.
+                    for identifier in &info.all_licenses_information_from_files {
+                        let is_recorded = other_licenses
+                            .iter()
+                            .any(|license| &license.license_identifier == identifier);
+
+                        if identifier.starts_with("LicenseRef-") && is_recorded.not() {
+                            other_licenses
+                                .push(OtherLicensingInformation::from_identifier_tag(identifier));
+                        }
+                    }
+                }
+            }
+
+            Ok(info)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    log::info!(target: "cargo_spdx", "collected {} packages", packages.len());
+
+    Ok(packages)
+}
+
+/// Describe the build profile the described packages are built with.
+fn get_profile_comment(metadata: &CrateMetadata, profile: &str) -> String {
+    let settings = metadata.profile_settings(profile);
+
+    if settings.is_empty() {
+        format!("Built with the '{}' profile.", profile)
+    } else {
+        format!(
+            "Built with the '{}' profile ({}).",
+            profile,
+            settings.join(", ")
+        )
+    }
+}
+
+/// Collect the relationships between the document and the packages it describes.
+///
+/// The document describes the root package (or every workspace member), each package
+/// depends on the packages it has edges to in the resolved dependency graph, proc-macros are
+/// build dependencies of the packages using them, and each package contains its analyzed
+/// files. Only relationships of the types selected by the user are kept.
+pub fn get_relationships(
+    args: &GenerateOptions,
+    metadata: &CrateMetadata,
+    refs: &PackageRefs,
+    packages: &[PackageInformation],
+) -> Result<Vec<Relationship>> {
+    let mut relationships = metadata
+        .described()?
+        .into_iter()
+        .map(|package| {
+            Relationship::new(
+                SpdxRef::document(),
+                RelationshipType::Describes,
+                refs.get(package),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for node in metadata.nodes() {
+        let package = refs.get(metadata.package(&node.id));
+
+        for dep in metadata.dependencies(node) {
+            relationships.push(Relationship::new(
+                package.clone(),
+                RelationshipType::DependsOn,
+                refs.get(dep),
+            ));
+
+            // Proc-macros are only used to build the package, never shipped with it.
+            if is_proc_macro(dep) {
+                relationships.push(Relationship::new(
+                    refs.get(dep),
+                    RelationshipType::BuildDependencyOf,
+                    package.clone(),
+                ));
+            }
+        }
+    }
+
+    for package in packages {
+        for file in &package.files {
+            relationships.push(Relationship::new(
+                package.package_spdx_identifier.clone(),
+                RelationshipType::Contains,
+                file.file_spdx_identifier.clone(),
+            ));
+        }
+    }
+
+    let types = args.relationship_types();
+    relationships.retain(|relationship| types.contains(&relationship.relationship_type));
+
+    // A document embedded in a larger one leaves describing the root package to that one.
+    if args.no_root_describes() {
+        relationships.retain(|relationship| relationship.spdx_element_id != SpdxRef::document());
+    }
+
+    Ok(relationships)
+}
+
+/// An SPDX SBOM document.
+#[derive(Debug, Clone, Builder)]
+pub struct Document {
+    /// The version of the SPD standard.
+    #[builder(setter(into))]
+    #[builder(default)]
+    pub spdx_version: SpdxVersion,
+
+    /// The license of the SPDX file itself.
+    #[builder(default)]
+    #[builder(setter(skip))]
+    pub data_license: DataLicense,
+
+    /// The identifier for the object the SBOM is referencing.
+    #[builder(default)]
+    #[builder(setter(skip))]
+    pub spdx_identifier: SpdxIdentifier,
+
+    /// The name of the SPDX file itself.
+    #[builder(setter(into))]
+    pub document_name: DocumentName,
+
+    /// A document-specific namespace URI.
+    #[builder(try_setter, setter(into))]
+    pub document_namespace: DocumentNamespace,
+
+    /// Other SPDX files referenced by this one.
+    #[builder(default)]
+    pub external_document_references: Vec<ExternalDocumentReference>,
+
+    /// The version of the SPDX license list used.
+    #[builder(setter(strip_option))]
+    #[builder(default)]
+    pub license_list_version: Option<LicenseListVersion>,
+
+    /// The creator of the SPDX file.
+    pub creator: Vec<Creator>,
+
+    /// The timestamp for when the SPDX file was created.
+    #[builder(setter(into))]
+    #[builder(default)]
+    pub created: Created,
+
+    /// Freeform comments about the creator of the SPDX file.
+    #[builder(setter(into))]
+    #[builder(default)]
+    pub creator_comment: Option<CreatorComment>,
+
+    /// Freeform comments about the SPDX file.
+    #[builder(setter(into))]
+    #[builder(default)]
+    pub document_comment: Option<DocumentComment>,
+
+    /// Information about the packages described by the SPDX file.
+    #[builder(default)]
+    pub package_information: Vec<PackageInformation>,
+
+    /// Licenses found which aren't on the SPDX license list.
+    #[builder(default)]
+    pub other_licensing_information_detected: Vec<OtherLicensingInformation>,
+
+    /// Relationships between the elements of the SPDX file.
+    #[builder(default)]
+    pub relationships: Vec<Relationship>,
+
+    /// Comments about elements of the SPDX file.
+    #[builder(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+impl Document {
+    /// Check at most `max` packages have a declared license which makes no assertion.
+    ///
+    /// Returns an error listing the packages if there are more.
+    pub fn check_unknown_licenses(&self, max: usize) -> Result<()> {
+        let unknown = self
+            .package_information
+            .iter()
+            .filter(|package| package.declared_license == SpdxValue::NoAssertion)
+            .map(|package| match &package.package_version {
+                Some(version) => format!("{} {}", package.package_name, version),
+                None => package.package_name.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        if unknown.len() > max {
+            return Err(anyhow!(
+                "{} packages have an unknown license, but at most {} are allowed:\n  {}",
+                unknown.len(),
+                max,
+                unknown.join("\n  ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Check every declared license is permitted by a policy of `allowed` and `denied`
+    /// licenses.
+    ///
+    /// If `allowed` is empty, any license which isn't denied is permitted, including an unknown
+    /// one. Otherwise packages with an unknown license aren't permitted, since they can't be
+    /// shown to be under an allowed license. Returns an error listing the packages which aren't
+    /// permitted, if any.
+    pub fn check_license_policy(&self, allowed: &[String], denied: &[String]) -> Result<()> {
+        let policy = LicensePolicy::new(allowed, denied)?;
+
+        let forbidden = self
+            .package_information
+            .iter()
+            .filter(|package| match &package.declared_license {
+                SpdxValue::Value(license) => policy.permits(&license.0).not(),
+                SpdxValue::NoAssertion => policy.permits_unknown().not(),
+            })
+            .map(|package| match &package.package_version {
+                Some(version) => format!(
+                    "{} {} ({})",
+                    package.package_name, version, package.declared_license
+                ),
+                None => format!("{} ({})", package.package_name, package.declared_license),
+            })
+            .collect::<Vec<_>>();
+
+        if forbidden.is_empty().not() {
+            return Err(anyhow!(
+                "{} packages have a license the policy doesn't permit:\n  {}",
+                forbidden.len(),
+                forbidden.join("\n  ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Check every relationship is between elements of the document.
+    ///
+    /// Returns an error naming the first identifier which doesn't resolve.
+    pub fn check_relationships(&self) -> Result<()> {
+        let document = SpdxRef::document();
+        let mut identifiers = HashSet::from([&document]);
+
+        for package in &self.package_information {
+            identifiers.insert(&package.package_spdx_identifier);
+            identifiers.extend(package.files.iter().map(|file| &file.file_spdx_identifier));
+        }
+
+        for relationship in &self.relationships {
+            for identifier in [
+                &relationship.spdx_element_id,
+                &relationship.related_spdx_element,
+            ] {
+                if identifiers.contains(identifier).not() {
+                    return Err(anyhow!(
+                        "relationship '{}' refers to {}, which isn't in the document",
+                        relationship,
+                        identifier
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The version of the SPDX standard being used.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, From)]
+#[display(fmt = "SPDX-{}.{}", major, minor)]
+pub struct SpdxVersion {
+    /// The major version.
+    pub major: u32,
+    /// The minor version.
+    pub minor: u32,
+}
+
+impl SpdxVersion {
+    /// The versions documents can be written in.
+    const SUPPORTED: [SpdxVersion; 2] = [
+        SpdxVersion { major: 2, minor: 2 },
+        SpdxVersion { major: 2, minor: 3 },
+    ];
+
+    /// Whether license information from files must be stated even when nothing is asserted.
+    ///
+    /// SPDX 2.3 made these fields optional.
+    pub fn requires_license_info_from_files(&self) -> bool {
+        self.major == 2 && self.minor < 3
+    }
+}
+
+impl Default for SpdxVersion {
+    fn default() -> Self {
+        SpdxVersion { major: 2, minor: 2 }
+    }
+}
+
+impl FromStr for SpdxVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SpdxVersion::SUPPORTED
+            .into_iter()
+            .find(|version| format!("{}.{}", version.major, version.minor) == s)
+            .ok_or_else(|| {
+                let supported = SpdxVersion::SUPPORTED
+                    .iter()
+                    .map(|version| format!("{}.{}", version.major, version.minor))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                anyhow!(
+                    "unsupported SPDX version '{}', expected one of: {}",
+                    s,
+                    supported
+                )
+            })
+    }
+}
+
+// Only has one representation, so there's no need
+// to store anything.
+/// The license of the SBOM file itself.
+#[derive(Debug, Display, Clone, Default)]
+#[display(fmt = "CC0-1.0")]
+pub struct DataLicense;
+
+/// The identifier for the artifact the SBOM is for.
+#[derive(Debug, Display, Clone, Default)]
+#[display(fmt = "SPDXRef-DOCUMENT")]
+pub struct SpdxIdentifier;
+
+/// The name of the SPDX file itself.
+#[derive(Debug, Display, Clone, From)]
+pub struct DocumentName(pub String);
+
+impl<'s> From<&'s str> for DocumentName {
+    fn from(string: &'s str) -> Self {
+        DocumentName(String::from(string))
+    }
+}
+
+/// A document-specific namespace URI.
+///
+/// Note that the SPDX 2.2 standard specifies an RFC 3986-compatible
+/// URL for this field (with the requirement that the URL _not_ include
+/// a fragment identifier.
+///
+/// Since we're generating an SBOM, rather than consuming them, we can
+/// afford to be more restrictive than the SPDX standard. We use the Rust
+/// `url` crate here, which follows the WHATWG's URL Living Standard.
+/// The URL Living Standard resolves some ambiguities in RFC 3986,
+/// and is not strictly compatible with it.
+#[derive(Debug, Display, Clone, From)]
+pub struct DocumentNamespace(pub Url);
+
+impl TryFrom<&str> for DocumentNamespace {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(DocumentNamespace(Url::parse(value)?))
+    }
+}
+
+/// A reference to another SPDX file.
+#[derive(Debug, Display, Clone)]
+#[display(fmt = "DocumentRef-{} {} {}", id_string, document_uri, checksum)]
+pub struct ExternalDocumentReference {
+    /// An ID string made of letters, numbers, '.', '-', and/or '+'.
+    pub id_string: IdString,
+    /// The namespace of the document.
+    pub document_uri: DocumentNamespace,
+    /// A checksum for the external document reference.
+    pub checksum: Checksum,
+}
+
+impl FromStr for ExternalDocumentReference {
+    type Err = Error;
+
+    /// Parse a reference of the form `<namespace>=<id>=<sha1>`.
+    ///
+    /// The ID may be given with or without its `DocumentRef-` prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            anyhow!(
+                "invalid external document reference '{}', expected '<namespace>=<id>=<sha1>'",
+                s
+            )
+        };
+
+        // Namespaces may contain '=' in their query, so split from the right.
+        let mut parts = s.rsplitn(3, '=');
+        let sha1 = parts.next().ok_or_else(invalid)?;
+        let id_string = parts.next().ok_or_else(invalid)?;
+        let namespace = parts.next().ok_or_else(invalid)?;
+        let id_string = id_string.strip_prefix("DocumentRef-").unwrap_or(id_string);
+
+        let is_id_string = id_string.is_empty().not()
+            && id_string
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'));
+        let is_sha1 = sha1.len() == 40 && sha1.chars().all(|c| c.is_ascii_hexdigit());
+
+        if is_id_string.not() || is_sha1.not() {
+            return Err(invalid());
+        }
+
+        Ok(ExternalDocumentReference {
+            id_string: IdString(id_string.to_owned()),
+            document_uri: DocumentNamespace::try_from(namespace)?,
+            checksum: Checksum {
+                algorithm: Algorithm::Sha1,
+                value: sha1.to_ascii_lowercase(),
+            },
+        })
+    }
+}
+
+/// An ID string made of letters, numbers, '.', '-', and/or '+'.
+#[derive(Debug, Display, Clone, From)]
+pub struct IdString(pub String);
+
+/// The version of the SPDX license list used.
+#[derive(Debug, Display, Clone)]
+#[display(fmt = "{}.{}", major, minor)]
+pub struct LicenseListVersion {
+    major: u32,
+    minor: u32,
+}
+
+/// The version of the SPDX license list that license expressions are validated against.
+///
+/// This is the list bundled with the `spdx` crate, so it must be kept in sync when that
+/// dependency is updated.
+const LICENSE_LIST_VERSION: LicenseListVersion = LicenseListVersion {
+    major: 3,
+    minor: 16,
+};
+
+/// The creator of the SPDX file.
+#[derive(Debug, Clone)]
+pub enum Creator {
+    #[allow(unused)]
+    Person {
+        name: String,
+        email: Option<String>,
+    },
+    #[allow(unused)]
+    Organization {
+        name: String,
+        email: Option<String>,
+    },
+    Tool {
+        name: String,
+    },
+}
+
+impl Creator {
+    /// Construct a new `Creator::Person`.
+    pub fn person(name: String, email: Option<String>) -> Self {
+        Creator::Person { name, email }
+    }
+
+    /// Parse a `Creator::Person` written in the `Name <email>` form Cargo uses for authors.
+    ///
+    /// Returns `None` if the name is empty.
+    pub fn parse_person(s: &str) -> Option<Self> {
+        let (name, email) = match s.split_once('<') {
+            Some((name, email)) => (name.trim(), email.trim().strip_suffix('>').map(str::trim)),
+            None => (s.trim(), None),
+        };
+
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(Creator::person(
+            name.to_owned(),
+            email
+                .filter(|email| email.is_empty().not())
+                .map(ToOwned::to_owned),
+        ))
+    }
+
+    /// Construct a new `Creator::Tool`.
+    pub fn tool(s: &str) -> Self {
+        Creator::Tool {
+            name: String::from(s),
+        }
+    }
+}
+
+impl Display for Creator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Creator::Person {
+                name,
+                email: Some(email),
+            } => write!(f, "Person: {} ({})", name, email),
+            Creator::Person { name, email: None } => write!(f, "Person: {}", name),
+            Creator::Organization {
+                name,
+                email: Some(email),
+            } => write!(f, "Organization: {} ({})", name, email),
+            Creator::Organization { name, email: None } => write!(f, "Organization: {}", name),
+            Creator::Tool { name } => write!(f, "Tool: {}", name),
+        }
+    }
+}
+
+/// The timestamp indicating when the SPDX file was created.
+#[derive(Debug, Clone, From)]
+
+pub struct Created(pub OffsetDateTime);
+
+impl Default for Created {
+    fn default() -> Self {
+        Created(OffsetDateTime::now_utc())
+    }
+}
+
+impl Display for Created {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let repr = {
+            // PANIC SAFETY: We need to ensure the `OffsetDateTime` is never
+            // invalid such that this formatting would panic.
+            let format_str = "[year]-[month]-[day]T[hour]:[minute]:[second]Z";
+            let format = format_description::parse(format_str).unwrap();
+            self.0.format(&format).unwrap()
+        };
+
+        write!(f, "{}", repr)
+    }
+}
+
+/// Freeform comment about the creator of the SPDX file.
+#[derive(Debug, Display, Clone, From)]
+pub struct CreatorComment(pub String);
+
+impl<'s> From<&'s str> for CreatorComment {
+    fn from(string: &'s str) -> Self {
+        CreatorComment(String::from(string))
+    }
+}
+
+/// Freeform comment about the SPDX file.
+#[derive(Debug, Display, Clone, From)]
+pub struct DocumentComment(pub String);
+
+impl<'s> From<&'s str> for DocumentComment {
+    fn from(string: &'s str) -> Self {
+        DocumentComment(String::from(string))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{self, JsonStyle};
+    use crate::test_support::{options, MetadataFixture, TempDir};
+    use std::ops::Not as _;
+
+    #[test]
+    fn namespaces_are_unique_for_each_run() {
+        let args = options(&["--host-url", "https://example.com/sboms"]);
+
+        let first = get_document_namespace(&args, "fixture.spdx", &[]).unwrap();
+        let second = get_document_namespace(&args, "fixture.spdx", &[]).unwrap();
+
+        assert_ne!(first.0, second.0);
+        assert!(first
+            .0
+            .as_str()
+            .starts_with("https://example.com/sboms/fixture.spdx-"));
+    }
+
+    #[test]
+    fn namespace_can_be_pinned() {
+        let args = options(&["--namespace", "https://example.com/pinned"]);
+
+        let namespace = get_document_namespace(&args, "fixture.spdx", &[]).unwrap();
+
+        assert_eq!(namespace.0.as_str(), "https://example.com/pinned");
+    }
+
+    #[test]
+    fn reproducible_builds_are_byte_identical() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("root", "0.1.0");
+        let a = fixture.package("a", "1.0.0");
+        let b = fixture.package("b", "2.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &b);
+        fixture.depend(&root, &a);
+        fixture.depend(&b, &a);
+
+        let outputs = (0..2)
+            .map(|_| {
+                let doc = fixture.build(&["--reproducible"]);
+
+                let mut output = vec![];
+                format::key_value::write(&mut output, &doc).unwrap();
+                format::json::write(&mut output, &doc, JsonStyle::Pretty).unwrap();
+                output
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    #[test]
+    fn diamond_dependencies_appear_once() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let left = fixture.package("left", "1.0.0");
+        let right = fixture.package("right", "1.0.0");
+        let shared = fixture.package("shared", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &left);
+        fixture.depend(&root, &right);
+        fixture.depend(&left, &shared);
+        fixture.depend(&right, &shared);
+
+        let doc = fixture.build(&[]);
+
+        let names = doc
+            .package_information
+            .iter()
+            .map(|package| package.package_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names.len(), 4, "{:?}", names);
+        assert_eq!(names.iter().filter(|name| **name == "shared").count(), 1);
+        assert_eq!(names[0], "fixture");
+
+        // The shared package is still depended on by both sides of the diamond.
+        let shared_id = &doc
+            .package_information
+            .iter()
+            .find(|package| package.package_name == "shared")
+            .unwrap()
+            .package_spdx_identifier;
+        let dependents = doc
+            .relationships
+            .iter()
+            .filter(|relationship| {
+                relationship.relationship_type == RelationshipType::DependsOn
+                    && &relationship.related_spdx_element == shared_id
+            })
+            .count();
+        assert_eq!(dependents, 2);
+    }
+
+    #[test]
+    fn same_version_from_different_sources_is_annotated() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let path = fixture.package("shared", "1.0.0");
+        let git = fixture.package_with("shared", "1.0.0", |json| {
+            let source = "git+https://example.com/shared#0123456789abcdef";
+            json["id"] = serde_json::json!(format!("shared 1.0.0 ({})", source));
+            json["source"] = serde_json::json!(source);
+        });
+        fixture.member(&root);
+        fixture.depend(&root, &path);
+        fixture.depend(&root, &git);
+
+        let doc = fixture.build(&[]);
+
+        let comments = doc
+            .annotations
+            .iter()
+            .map(|annotation| annotation.annotation_comment.as_str())
+            .filter(|comment| comment.starts_with("Multiple versions"))
+            .collect::<Vec<_>>();
+        let expected = "Multiple versions of 'shared' are present: 1.0.0 (path), 1.0.0 \
+                        (git+https://example.com/shared#0123456789abcdef).";
+
+        assert_eq!(comments, [expected, expected]);
+    }
+
+    #[test]
+    fn two_versions_of_a_crate_are_annotated() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let old = fixture.package("shared", "0.9.0");
+        let new = fixture.package("shared", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &old);
+        fixture.depend(&root, &new);
+
+        let doc = fixture.build(&[]);
+
+        let annotated = doc
+            .annotations
+            .iter()
+            .filter(|annotation| {
+                annotation.annotation_comment
+                    == "Multiple versions of 'shared' are present: 0.9.0, 1.0.0."
+            })
+            .map(|annotation| annotation.spdx_identifier_reference.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(annotated, ["SPDXRef-shared-0.9.0", "SPDXRef-shared-1.0.0"]);
+    }
+
+    /// Build a document from a chain of dependencies, 'fixture' -> 'a' -> 'b' -> 'c', where
+    /// 'fixture' also depends on 'c' directly, and return the names of its packages and
+    /// annotations.
+    fn build_chain(args: &[&str]) -> (Vec<String>, Vec<String>) {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let a = fixture.package("a", "1.0.0");
+        let b = fixture.package("b", "1.0.0");
+        let c = fixture.package("c", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &a);
+        fixture.depend(&a, &b);
+        fixture.depend(&b, &c);
+        fixture.depend(&root, &c);
+
+        let doc = fixture.build(args);
+
+        let mut names = doc
+            .package_information
+            .into_iter()
+            .map(|package| package.package_name)
+            .collect::<Vec<_>>();
+        names.sort();
+
+        let annotations = doc
+            .annotations
+            .into_iter()
+            .map(|annotation| annotation.annotation_comment)
+            .collect();
+
+        (names, annotations)
+    }
+
+    #[test]
+    fn dependencies_past_the_max_depth_are_left_out() {
+        let (names, annotations) = build_chain(&["--max-depth", "1"]);
+
+        // 'c' is kept, since its shortest path from the root is a single edge.
+        assert_eq!(names, ["a", "c", "fixture"]);
+        assert!(
+            annotations.contains(&String::from(
+                "Dependencies more than 1 edges from the root package were omitted."
+            )),
+            "{:?}",
+            annotations
+        );
+    }
+
+    #[test]
+    fn max_depth_zero_keeps_only_the_root() {
+        let (names, annotations) = build_chain(&["--max-depth", "0"]);
+
+        assert_eq!(names, ["fixture"]);
+        assert!(annotations
+            .iter()
+            .any(|annotation| annotation.contains("more than 0 edges")));
+    }
+
+    #[test]
+    fn max_depth_covering_the_graph_omits_nothing() {
+        let (names, annotations) = build_chain(&["--max-depth", "2"]);
+
+        assert_eq!(names, ["a", "b", "c", "fixture"]);
+        assert!(annotations
+            .iter()
+            .all(|annotation| annotation.contains("omitted").not()));
+    }
+
+    #[test]
+    fn invalid_license_expressions_are_counted() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package_with("fixture", "0.1.0", |json| {
+            json["license"] = serde_json::json!("MIT");
+        });
+        let invalid = fixture.package_with("invalid", "1.0.0", |json| {
+            json["license"] = serde_json::json!("MIT OR");
+        });
+        let unlicensed = fixture.package("unlicensed", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &invalid);
+        fixture.depend(&root, &unlicensed);
+
+        let (options, metadata) = fixture.load(&[]);
+        let refs = PackageRefs::new(&metadata);
+        let mut invalid_licenses = 0;
+        let packages = get_package_information(
+            &options,
+            &metadata,
+            &refs,
+            None,
+            &mut vec![],
+            &mut invalid_licenses,
+        )
+        .unwrap();
+
+        // Packages with no license at all aren't counted as invalid.
+        assert_eq!(invalid_licenses, 1);
+
+        let invalid = packages
+            .iter()
+            .find(|package| package.package_name == "invalid")
+            .unwrap();
+        assert_eq!(invalid.declared_license, SpdxValue::NoAssertion);
+        assert!(invalid
+            .comments_on_license
+            .as_deref()
+            .unwrap()
+            .contains("'MIT OR' failed to parse"));
+    }
+
+    #[test]
+    fn default_relationships_include_files_and_proc_macros() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let derive = fixture.package_with("derive", "1.0.0", |json| {
+            json["targets"][0]["kind"] = serde_json::json!(["proc-macro"]);
+        });
+        fixture.member(&root);
+        fixture.depend(&root, &derive);
+        dir.write("fixture/Cargo.toml", "[package]\nname = \"fixture\"\n");
+        dir.write("fixture/src/lib.rs", "");
+
+        let doc = fixture.build(&["--with-files"]);
+        let relationships = doc
+            .relationships
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert!(relationships.contains(&String::from(
+            "SPDXRef-derive-1.0.0 BUILD_DEPENDENCY_OF SPDXRef-fixture-0.1.0"
+        )));
+        assert!(relationships.iter().any(|relationship| relationship
+            .starts_with("SPDXRef-fixture-0.1.0 CONTAINS SPDXRef-fixture-0.1.0-File-")));
+
+        let doc = fixture.build(&["--with-files", "--relationships", "describes,depends_on"]);
+        assert!(doc.relationships.iter().all(|relationship| matches!(
+            relationship.relationship_type,
+            RelationshipType::Describes | RelationshipType::DependsOn
+        )));
+    }
+
+    #[test]
+    fn source_date_epoch_is_parsed() {
+        let created = parse_source_date_epoch(" 1700000000\n").unwrap();
+        assert_eq!(created.0.unix_timestamp(), 1_700_000_000);
+    }
+
+    #[test]
+    fn malformed_source_date_epoch_is_named() {
+        let error = parse_source_date_epoch("yesterday").unwrap_err();
+        assert_eq!(error.to_string(), "invalid SOURCE_DATE_EPOCH 'yesterday'");
+
+        let error = parse_source_date_epoch(&i64::MAX.to_string()).unwrap_err();
+        assert!(error.to_string().starts_with("invalid SOURCE_DATE_EPOCH"));
+    }
+
+    /// Reconstruct a command line from string arguments.
+    fn invocation(command_line: &[&str], extra_words: &[&str]) -> String {
+        let extra_words = extra_words
+            .iter()
+            .map(|word| word.to_string())
+            .collect::<Vec<_>>();
+
+        get_invocation(command_line.iter().map(|arg| arg.to_string()), &extra_words)
+    }
+
+    #[test]
+    fn separate_option_values_are_redacted() {
+        assert_eq!(
+            invocation(&["cargo", "spdx", "--token", "abc123", "--offline"], &[]),
+            "cargo spdx --token <redacted> --offline"
+        );
+    }
+
+    #[test]
+    fn joined_option_values_are_redacted() {
+        assert_eq!(
+            invocation(&["cargo", "spdx", "--token=abc123", "--offline"], &[]),
+            "cargo spdx --token=<redacted> --offline"
+        );
+    }
+
+    #[test]
+    fn name_value_pairs_are_redacted() {
+        assert_eq!(
+            invocation(&["env", "NAME_SECRET=x", "NAME=y", "cargo"], &[]),
+            "env NAME_SECRET=<redacted> NAME=y cargo"
+        );
+    }
+
+    #[test]
+    fn secrets_are_matched_ignoring_case() {
+        assert_eq!(
+            invocation(&["cargo", "spdx", "--API-Key", "x", "PASSWORD=y"], &[]),
+            "cargo spdx --API-Key <redacted> PASSWORD=<redacted>"
+        );
+    }
+
+    #[test]
+    fn extra_words_are_redacted() {
+        assert_eq!(
+            invocation(
+                &["cargo", "spdx", "--credential", "x", "--host-url", "y"],
+                &["Credential"]
+            ),
+            "cargo spdx --credential <redacted> --host-url y"
+        );
+        assert_eq!(
+            invocation(&["cargo", "spdx", "--credential", "x"], &[]),
+            "cargo spdx --credential x"
+        );
+    }
+
+    #[test]
+    fn arguments_with_spaces_or_quotes_are_quoted() {
+        assert_eq!(
+            invocation(
+                &[
+                    "cargo",
+                    "spdx",
+                    "--creator",
+                    "A Person",
+                    "--comment",
+                    "it's \"here\""
+                ],
+                &[]
+            ),
+            r#"cargo spdx --creator 'A Person' --comment 'it'\''s "here"'"#
+        );
+    }
+
+    /// A document with a package under each of `licenses`, and one with no license.
+    fn licensed_document(licenses: &[&str]) -> Document {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        fixture.member(&root);
+
+        for (index, license) in licenses.iter().enumerate() {
+            let id = fixture.package_with(&format!("licensed-{}", index), "1.0.0", |json| {
+                json["license"] = serde_json::json!(license);
+            });
+            fixture.depend(&root, &id);
+        }
+
+        fixture.build(&[])
+    }
+
+    #[test]
+    fn unknown_licenses_are_only_permitted_without_an_allowlist() {
+        let doc = licensed_document(&["MIT", "Apache-2.0"]);
+
+        doc.check_license_policy(&[], &[String::from("GPL-3.0-only")])
+            .unwrap();
+
+        let error = doc
+            .check_license_policy(&[String::from("MIT"), String::from("Apache-2.0")], &[])
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.starts_with("1 packages have a license"), "{}", error);
+        assert!(error.contains("fixture 0.1.0 (NOASSERTION)"), "{}", error);
+    }
+
+    #[test]
+    fn declared_licenses_are_checked_against_the_policy() {
+        let doc = licensed_document(&["MIT", "GPL-3.0-only"]);
+
+        let error = doc
+            .check_license_policy(&[], &[String::from("GPL-3.0-only")])
+            .unwrap_err()
+            .to_string();
+
+        assert!(
+            error.contains("licensed-1 1.0.0 (GPL-3.0-only)"),
+            "{}",
+            error
+        );
+        assert!(error.contains("licensed-0").not(), "{}", error);
+    }
+}
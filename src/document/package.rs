@@ -0,0 +1,1208 @@
+//! Defines the package information section of the SPDX document.
+
+use crate::binary::AuditablePackage;
+use crate::cargo::{CrateMetadata, LockChecksums};
+use crate::document::checksum::{Algorithm, Checksum};
+use crate::document::file::{
+    get_package_files, FileAnalysis, FileInformation, PackageVerificationCode,
+};
+use crate::document::license::{normalize_expression, OtherLicensingInformation};
+use crate::document::Creator;
+use crate::GenerateOptions;
+use anyhow::Result;
+use cargo_metadata::{Package, PackageId};
+use derive_more::{Display, From};
+use once_cell::sync::Lazy;
+use packageurl::PackageUrl;
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::ops::Not as _;
+use std::path::{Path, PathBuf};
+
+/// Matches a line of a license file which is a copyright notice.
+static COPYRIGHT_NOTICE: Lazy<Regex> = Lazy::new(|| {
+    // PANIC SAFETY: The pattern is a valid regular expression.
+    Regex::new(r"(?i)^copyright\s+(\(c\)\s+|©\s+)?\d{4}").unwrap()
+});
+
+/// The comment on the license of a package when no license was concluded for it.
+const NOT_CONCLUDED: &str =
+    "No license was concluded, since the package wasn't reviewed beyond its manifest.";
+
+/// The start of every license comment generated for a package, so they can be told apart from
+/// comments written by reviewers when a prior document is merged.
+pub const GENERATED_COMMENT_PREFIX: &str = "Generated by cargo-spdx: ";
+
+/// Information about a single package described by the SPDX file.
+#[derive(Debug, Clone)]
+pub struct PackageInformation {
+    /// The name of the package.
+    pub package_name: String,
+
+    /// The identifier for the package within the document.
+    pub package_spdx_identifier: SpdxRef,
+
+    /// The version of the package.
+    pub package_version: Option<String>,
+
+    /// The file name of the package's distributed artifact.
+    pub package_file_name: Option<String>,
+
+    /// The original author of the package.
+    pub package_originator: Option<Creator>,
+
+    /// Where the package can be downloaded from.
+    pub package_download_location: SpdxValue<String>,
+
+    /// Whether the files of the package were analyzed.
+    pub files_analyzed: bool,
+
+    /// A code identifying the contents of the package, if its files were analyzed.
+    pub package_verification_code: Option<PackageVerificationCode>,
+
+    /// Checksums of the package's distributed artifact.
+    pub package_checksum: Vec<Checksum>,
+
+    /// How the package was obtained, if it wasn't downloaded from a registry.
+    pub source_information: Option<String>,
+
+    /// The license the SPDX file creator concluded the package has.
+    pub concluded_license: SpdxValue<LicenseExpression>,
+
+    /// The licenses named by the files of the package, if they were analyzed.
+    pub all_licenses_information_from_files: Vec<String>,
+
+    /// The license declared by the authors of the package.
+    pub declared_license: SpdxValue<LicenseExpression>,
+
+    /// Comments on how the license information for the package was determined.
+    pub comments_on_license: Option<String>,
+
+    /// Copyright notices for the package.
+    pub copyright_text: SpdxValue<String>,
+
+    /// A short description of the package.
+    pub package_summary_description: Option<String>,
+
+    /// A full description of the package.
+    pub package_detailed_description: Option<String>,
+
+    /// References to the package in external systems.
+    pub external_reference: Vec<ExternalReference>,
+
+    /// A freeform comment about the package.
+    pub package_comment: Option<String>,
+
+    /// Notices which must be reproduced when distributing the package, from its NOTICE files.
+    pub package_attribution_text: Vec<String>,
+
+    /// The files in the package, if they were analyzed.
+    pub files: Vec<FileInformation>,
+}
+
+impl PackageInformation {
+    /// Construct the package information from a package in the crate metadata.
+    ///
+    /// Any licenses found which aren't on the SPDX license list are added to
+    /// `other_licenses`. If `conclude_declared` is set, the concluded license is the
+    /// declared one. If `normalize_licenses` is set, the declared license is rewritten with
+    /// official SPDX identifiers. The source of path dependencies is given relative to
+    /// `workspace_root`. Package URLs are given the type and namespace in `purl_prefix`.
+    pub fn from_metadata_package(
+        package: &Package,
+        package_spdx_identifier: SpdxRef,
+        workspace_root: &Path,
+        other_licenses: &mut Vec<OtherLicensingInformation>,
+        conclude_declared: bool,
+        normalize_licenses: bool,
+        purl_prefix: PurlPrefix<'_>,
+    ) -> Self {
+        let (mut declared_license, mut comments_on_license) =
+            get_declared_license(package, normalize_licenses);
+
+        // Packages without an SPDX license expression may instead point to a license file.
+        if package.license.is_none() && package.license_file.is_some() {
+            match OtherLicensingInformation::from_license_file(package, &package_spdx_identifier.0)
+            {
+                Ok(other_license) => {
+                    declared_license = SpdxValue::Value(LicenseExpression(
+                        other_license.license_identifier.clone(),
+                    ));
+                    comments_on_license = Some(format!(
+                        "The declared license is the contents of the package's license file, \
+                         recorded as {}.",
+                        other_license.license_identifier
+                    ));
+                    other_licenses.push(other_license);
+                }
+                Err(e) => {
+                    log::warn!(target: "cargo_spdx", "{}", e);
+
+                    comments_on_license = Some(format!(
+                        "The crate manifest has no license field, and its license file \
+                         couldn't be read: {}.",
+                        e
+                    ));
+                }
+            }
+        }
+
+        let mut concluded_license = SpdxValue::NoAssertion;
+
+        match &declared_license {
+            SpdxValue::Value(_) if conclude_declared => {
+                concluded_license = declared_license.clone();
+
+                let basis = "The concluded license is the declared license, taken as-is \
+                             without analyzing the package's files.";
+                comments_on_license = Some(add_sentence(comments_on_license, basis));
+            }
+            // That no license was concluded is noted once any prior conclusions are merged.
+            SpdxValue::Value(_) | SpdxValue::NoAssertion => {}
+        }
+
+        let source = PackageSource::of(package);
+
+        PackageInformation {
+            package_name: package.name.clone(),
+            package_spdx_identifier,
+            package_version: Some(package.version.to_string()),
+            package_file_name: get_file_name(package, &source),
+            package_originator: package
+                .authors
+                .first()
+                .and_then(|author| Creator::parse_person(author)),
+            package_download_location: get_download_location(package, &source),
+            files_analyzed: false,
+            package_verification_code: None,
+            package_checksum: vec![],
+            source_information: get_source_information(package, &source, workspace_root),
+            concluded_license,
+            all_licenses_information_from_files: vec![],
+            declared_license,
+            comments_on_license: comments_on_license.map(|comment| generated_comment(&comment)),
+            copyright_text: get_copyright_text(package),
+            package_summary_description: package
+                .description
+                .as_ref()
+                .and_then(|description| description.lines().next())
+                .map(|summary| summary.trim().to_owned()),
+            package_detailed_description: package.description.clone(),
+            external_reference: get_external_references(package, &source, purl_prefix),
+            package_comment: None,
+            package_attribution_text: get_attribution_text(package),
+            files: vec![],
+        }
+    }
+
+    /// Construct the package information from a package recorded in a binary built with
+    /// `cargo auditable`.
+    ///
+    /// Binaries only record each package's name, version, and kind of source, so nothing is
+    /// asserted about licenses or copyright. Package URLs are given the type and namespace in
+    /// `purl_prefix`.
+    pub fn from_auditable_package(
+        package: &AuditablePackage,
+        package_spdx_identifier: SpdxRef,
+        purl_prefix: PurlPrefix<'_>,
+    ) -> Self {
+        let from_crates_io = package.source == "crates.io";
+
+        // PANIC SAFETY: The type is validated when the arguments are parsed, and crate names are
+        // never empty.
+        let mut purl = PackageUrl::new(purl_prefix.purl_type, package.name.clone()).unwrap();
+        purl.with_version(package.version.clone());
+
+        if let Some(namespace) = purl_prefix.namespace {
+            purl.with_namespace(namespace);
+        }
+
+        PackageInformation {
+            package_name: package.name.clone(),
+            package_spdx_identifier,
+            package_version: Some(package.version.clone()),
+            package_file_name: from_crates_io
+                .then(|| format!("{}-{}.crate", package.name, package.version)),
+            package_originator: None,
+            package_download_location: if from_crates_io {
+                SpdxValue::Value(format!(
+                    "https://static.crates.io/crates/{name}/{name}-{version}.crate",
+                    name = package.name,
+                    version = package.version
+                ))
+            } else {
+                SpdxValue::NoAssertion
+            },
+            files_analyzed: false,
+            package_verification_code: None,
+            package_checksum: vec![],
+            source_information: from_crates_io.not().then(|| {
+                format!(
+                    "Recorded in the binary as from source '{}'.",
+                    package.source
+                )
+            }),
+            concluded_license: SpdxValue::NoAssertion,
+            all_licenses_information_from_files: vec![],
+            declared_license: SpdxValue::NoAssertion,
+            comments_on_license: Some(generated_comment(
+                "The binary records no license information for the package.",
+            )),
+            copyright_text: SpdxValue::NoAssertion,
+            package_summary_description: None,
+            package_detailed_description: None,
+            external_reference: vec![ExternalReference::purl(purl.to_string())],
+            package_comment: None,
+            package_attribution_text: vec![],
+            files: vec![],
+        }
+    }
+
+    /// Record the checksum of the package's `.crate` archive from the lockfile.
+    ///
+    /// Only packages from a registry have archives, and a warning is logged for any of them
+    /// without a recorded checksum.
+    pub fn add_lock_checksum(&mut self, package: &Package, checksums: &LockChecksums) {
+        match PackageSource::of(package) {
+            PackageSource::CratesIo | PackageSource::Registry => {}
+            PackageSource::Git { .. } | PackageSource::Path => return,
+        }
+
+        match checksums.get(package) {
+            Some(value) => self.package_checksum.push(Checksum {
+                algorithm: Algorithm::Sha256,
+                value: value.to_owned(),
+            }),
+            None => log::warn!(
+                target: "cargo_spdx",
+                "no checksum of version {} of package '{}' is recorded in the lockfile",
+                package.version,
+                package.name
+            ),
+        }
+    }
+
+    /// Note in the comment on the license that no license was concluded, if one was declared
+    /// but none was concluded.
+    ///
+    /// This is done once conclusions from any prior document have been merged, and only adds to
+    /// generated comments, leaving those written by reviewers as they are.
+    pub fn note_unconcluded_license(&mut self) {
+        let unconcluded = matches!(
+            (&self.declared_license, &self.concluded_license),
+            (SpdxValue::Value(_), SpdxValue::NoAssertion)
+        );
+
+        if unconcluded.not() {
+            return;
+        }
+
+        self.comments_on_license = match self.comments_on_license.take() {
+            None => Some(generated_comment(NOT_CONCLUDED)),
+            Some(comment) if is_generated_comment(&comment) => {
+                Some(add_sentence(Some(comment), NOT_CONCLUDED))
+            }
+            Some(comment) => Some(comment),
+        };
+    }
+
+    /// Sort the external references by category, type, and locator, so they're written out
+    /// in the same order however they were found.
+    pub fn sort_external_references(&mut self) {
+        self.external_reference.sort_by(|a, b| {
+            (a.category, &a.reference_type, &a.locator).cmp(&(
+                b.category,
+                &b.reference_type,
+                &b.locator,
+            ))
+        });
+    }
+
+    /// Record a local path package as the crates.io package it's published as.
+    ///
+    /// The download location, file name, and external references become those of the
+    /// crates.io package.
+    pub fn publish_from_crates_io(&mut self, package: &Package, purl_prefix: PurlPrefix<'_>) {
+        let source = PackageSource::CratesIo;
+
+        self.package_file_name = get_file_name(package, &source);
+        self.package_download_location = get_download_location(package, &source);
+        self.source_information = None;
+
+        // References found elsewhere, like advisories, are kept.
+        self.external_reference
+            .retain(|reference| reference.reference_type != "purl");
+        self.external_reference
+            .insert(0, get_purl(package, &source, purl_prefix));
+    }
+
+    /// Analyze the files of the package, as set out by `analysis`.
+    pub fn analyze_files(&mut self, package: &Package, analysis: &FileAnalysis<'_>) -> Result<()> {
+        self.files = get_package_files(package, &self.package_spdx_identifier, analysis)?;
+        self.files_analyzed = true;
+
+        let mut licenses = self
+            .files
+            .iter()
+            .flat_map(|file| file.license_information_in_file.iter().cloned())
+            .collect::<Vec<_>>();
+        licenses.sort();
+        licenses.dedup();
+        self.all_licenses_information_from_files = licenses;
+
+        self.package_verification_code = Some(PackageVerificationCode::from_files(&self.files));
+        Ok(())
+    }
+
+    /// Record who contributed to each analyzed file of the package, whose files are named
+    /// relative to `strip_prefix`.
+    ///
+    /// Contributors are the authors in `file_authors`, from git history, if it's available.
+    /// Otherwise every file is credited to the people listed in the package's `AUTHORS` file.
+    pub fn add_file_contributors(
+        &mut self,
+        package: &Package,
+        strip_prefix: &Path,
+        file_authors: Option<&HashMap<PathBuf, BTreeSet<String>>>,
+    ) {
+        let file_authors = match file_authors {
+            Some(file_authors) => file_authors,
+            None => {
+                let authors = match package.manifest_path.parent() {
+                    Some(dir) => read_authors_file(dir.as_std_path()),
+                    None => vec![],
+                };
+
+                for file in &mut self.files {
+                    file.file_contributor = authors.clone();
+                }
+
+                return;
+            }
+        };
+
+        for file in &mut self.files {
+            // Files outside the strip prefix are named by their absolute paths, which replace
+            // the prefix when joined.
+            let path = strip_prefix.join(file.file_name.trim_start_matches("./"));
+            let path = fs::canonicalize(&path).unwrap_or(path);
+
+            if let Some(authors) = file_authors.get(&path) {
+                file.file_contributor = authors.iter().cloned().collect();
+            }
+        }
+    }
+}
+
+/// Read the people listed in the `AUTHORS` file at the top of a package directory, skipping
+/// blank lines and comments.
+fn read_authors_file(package_dir: &Path) -> Vec<String> {
+    let contents = match fs::read_to_string(package_dir.join("AUTHORS")) {
+        Ok(contents) => contents,
+        Err(_) => return vec![],
+    };
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.is_empty().not() && line.starts_with('#').not())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Where a package was obtained from.
+enum PackageSource<'p> {
+    /// The crates.io registry.
+    CratesIo,
+    /// Some other registry.
+    Registry,
+    /// A git repository, possibly pinned to a revision.
+    Git {
+        /// The URL of the repository, without any query parameters.
+        url: &'p str,
+        /// The revision of the repository.
+        revision: Option<&'p str>,
+    },
+    /// A local path.
+    Path,
+}
+
+impl<'p> PackageSource<'p> {
+    /// Determine the source of a package.
+    fn of(package: &'p Package) -> Self {
+        let source = match &package.source {
+            Some(source) => source,
+            None => return PackageSource::Path,
+        };
+
+        if source.is_crates_io() {
+            return PackageSource::CratesIo;
+        }
+
+        match source.repr.strip_prefix("git+") {
+            Some(repr) => {
+                // Cargo records the resolved revision as the fragment, and the requested
+                // branch, tag, or revision as a query parameter.
+                let (url, revision) = match repr.split_once('#') {
+                    Some((url, revision)) => (url, Some(revision)),
+                    None => (repr, None),
+                };
+                let url = url.split_once('?').map_or(url, |(url, _)| url);
+
+                PackageSource::Git { url, revision }
+            }
+            None => PackageSource::Registry,
+        }
+    }
+}
+
+/// Get the file name of the `.crate` archive of a package from a registry.
+fn get_file_name(package: &Package, source: &PackageSource<'_>) -> Option<String> {
+    match source {
+        PackageSource::CratesIo | PackageSource::Registry => {
+            Some(format!("{}-{}.crate", package.name, package.version))
+        }
+        PackageSource::Git { .. } | PackageSource::Path => None,
+    }
+}
+
+/// Get the location the package can be downloaded from.
+fn get_download_location(package: &Package, source: &PackageSource<'_>) -> SpdxValue<String> {
+    match source {
+        PackageSource::CratesIo => SpdxValue::Value(format!(
+            "https://static.crates.io/crates/{name}/{name}-{version}.crate",
+            name = package.name,
+            version = package.version
+        )),
+        PackageSource::Git {
+            url,
+            revision: Some(revision),
+        } => SpdxValue::Value(format!("git+{}@{}", url, revision)),
+        PackageSource::Git {
+            url,
+            revision: None,
+        } => SpdxValue::Value(format!("git+{}", url)),
+        PackageSource::Registry | PackageSource::Path => SpdxValue::NoAssertion,
+    }
+}
+
+/// Describe how the package was obtained, if it wasn't downloaded from a registry.
+///
+/// Paths are given relative to `workspace_root` where possible.
+fn get_source_information(
+    package: &Package,
+    source: &PackageSource<'_>,
+    workspace_root: &Path,
+) -> Option<String> {
+    match source {
+        PackageSource::Path => {
+            let dir = package.manifest_path.parent()?.as_std_path();
+            let dir = match dir.strip_prefix(workspace_root) {
+                Ok(relative) if relative.as_os_str().is_empty() => Path::new("."),
+                Ok(relative) => relative,
+                Err(_) => dir,
+            };
+
+            Some(format!(
+                "Obtained from local path {} relative to workspace",
+                dir.display()
+            ))
+        }
+        PackageSource::Git {
+            url,
+            revision: Some(revision),
+        } => Some(format!(
+            "Obtained from git repository {} at revision {}",
+            url, revision
+        )),
+        PackageSource::Git {
+            url,
+            revision: None,
+        } => Some(format!("Obtained from git repository {}", url)),
+        PackageSource::CratesIo | PackageSource::Registry => None,
+    }
+}
+
+/// Get the references to the package in external systems.
+///
+/// Every package has a package URL, and packages with a repository link to it.
+fn get_external_references(
+    package: &Package,
+    source: &PackageSource<'_>,
+    purl_prefix: PurlPrefix<'_>,
+) -> Vec<ExternalReference> {
+    let mut references = vec![get_purl(package, source, purl_prefix)];
+
+    if let Some(repository) = &package.repository {
+        references.push(ExternalReference::vcs(repository.clone()));
+    }
+
+    references
+}
+
+/// Get the package URL reference for the package.
+///
+/// Components are percent-encoded as the package URL specification requires. Git dependencies
+/// carry a `vcs_url` qualifier identifying the checked-out revision, since they may not exist
+/// on crates.io. Path dependencies can't be resolved from their package URL, so the local path
+/// is noted in the reference comment instead.
+fn get_purl(
+    package: &Package,
+    source: &PackageSource<'_>,
+    purl_prefix: PurlPrefix<'_>,
+) -> ExternalReference {
+    // PANIC SAFETY: The type is validated when the arguments are parsed, and crate names are
+    // never empty.
+    let mut purl = PackageUrl::new(purl_prefix.purl_type, package.name.clone()).unwrap();
+    purl.with_version(package.version.to_string());
+
+    if let Some(namespace) = purl_prefix.namespace {
+        purl.with_namespace(namespace);
+    }
+
+    match source {
+        PackageSource::Git { url, revision } => {
+            let vcs_url = match revision {
+                Some(revision) => format!("git+{}@{}", url, revision),
+                None => format!("git+{}", url),
+            };
+
+            // PANIC SAFETY: "vcs_url" is a valid qualifier key.
+            purl.add_qualifier("vcs_url", vcs_url).unwrap();
+            ExternalReference::purl(purl.to_string())
+        }
+        PackageSource::Path => {
+            let path = package
+                .manifest_path
+                .parent()
+                .unwrap_or(package.manifest_path.as_path());
+
+            let mut reference = ExternalReference::purl(purl.to_string());
+            reference.comment = Some(format!("Local path dependency at '{}'.", path));
+            reference
+        }
+        PackageSource::CratesIo | PackageSource::Registry => {
+            ExternalReference::purl(purl.to_string())
+        }
+    }
+}
+
+/// Get the contents of the NOTICE files in the package's root directory.
+///
+/// Licenses like Apache-2.0 require these to be preserved when the package is distributed.
+fn get_attribution_text(package: &Package) -> Vec<String> {
+    let entries = match package.manifest_path.parent().map(fs::read_dir) {
+        Some(Ok(entries)) => entries,
+        _ => return vec![],
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .to_uppercase()
+                .starts_with("NOTICE")
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+
+    // Directory entries come back in no particular order.
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| match fs::read_to_string(&path) {
+            Ok(contents) => Some(contents.trim().to_owned()),
+            Err(e) => {
+                log::warn!(
+                    target: "cargo_spdx",
+                    "failed to read '{}' of package '{}': {}",
+                    path.display(),
+                    package.name,
+                    e
+                );
+                None
+            }
+        })
+        .filter(|contents| contents.is_empty().not())
+        .collect()
+}
+
+/// Get the copyright notices from the license files of the package.
+///
+/// Only `LICENSE*` and `COPYING*` files at the top level of the package directory are
+/// scanned, so `target/` and vendored dependencies are never walked.
+fn get_copyright_text(package: &Package) -> SpdxValue<String> {
+    let entries = match package.manifest_path.parent().map(fs::read_dir) {
+        Some(Ok(entries)) => entries,
+        _ => return SpdxValue::NoAssertion,
+    };
+
+    let mut notices = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_uppercase();
+            name.starts_with("LICENSE") || name.starts_with("COPYING")
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .flat_map(|contents| {
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| COPYRIGHT_NOTICE.is_match(line))
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // Directory entries come back in no particular order.
+    notices.sort();
+    notices.dedup();
+
+    if notices.is_empty() {
+        SpdxValue::NoAssertion
+    } else {
+        SpdxValue::Value(notices.join("\n"))
+    }
+}
+
+/// Mark a comment as generated, rather than written by a reviewer.
+fn generated_comment(comment: &str) -> String {
+    format!("{}{}", GENERATED_COMMENT_PREFIX, comment)
+}
+
+/// Check whether a comment was generated, rather than written by a reviewer.
+pub fn is_generated_comment(comment: &str) -> bool {
+    comment.starts_with(GENERATED_COMMENT_PREFIX)
+}
+
+/// Add a sentence to the end of a comment, if there is one.
+fn add_sentence(comment: Option<String>, sentence: &str) -> String {
+    match comment {
+        Some(comment) => format!("{} {}", comment, sentence),
+        None => String::from(sentence),
+    }
+}
+
+/// Get the license declared in the package's manifest.
+///
+/// Falls back to `NOASSERTION` if no license is declared or if the declared
+/// license isn't a valid SPDX license expression, with a comment saying which. If
+/// `normalize` is set, the expression is rewritten with official SPDX identifiers, and
+/// a comment recording the original expression is returned if that changed it.
+fn get_declared_license(
+    package: &Package,
+    normalize: bool,
+) -> (SpdxValue<LicenseExpression>, Option<String>) {
+    let license = match &package.license {
+        Some(license) => license,
+        None => {
+            let comment = String::from("The crate manifest has no license field.");
+            return (SpdxValue::NoAssertion, Some(comment));
+        }
+    };
+
+    if normalize {
+        match normalize_expression(license) {
+            Some(normalized) if &normalized == license => {}
+            Some(normalized) => {
+                log::info!(
+                    target: "cargo_spdx",
+                    "normalized the license of package '{}' from '{}' to '{}'",
+                    package.name,
+                    license,
+                    normalized
+                );
+
+                let comment = format!("The declared license was normalized from '{}'.", license);
+                return (
+                    SpdxValue::Value(LicenseExpression(normalized)),
+                    Some(comment),
+                );
+            }
+            None => {}
+        }
+    }
+
+    match spdx::Expression::parse(license) {
+        Ok(_) => (SpdxValue::Value(LicenseExpression(license.clone())), None),
+        Err(e) => {
+            log::warn!(
+                target: "cargo_spdx",
+                "package '{}' has an invalid license expression '{}': {}",
+                package.name,
+                license,
+                e
+            );
+
+            let comment = format!(
+                "The declared license '{}' failed to parse as an SPDX license expression: {}.",
+                license, e.reason
+            );
+            (SpdxValue::NoAssertion, Some(comment))
+        }
+    }
+}
+
+/// An identifier for an element of the SPDX document.
+#[derive(Debug, Display, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, From)]
+#[display(fmt = "SPDXRef-{}", _0)]
+pub struct SpdxRef(pub String);
+
+impl SpdxRef {
+    /// Get the identifier of the document itself.
+    pub fn document() -> Self {
+        SpdxRef(String::from("DOCUMENT"))
+    }
+
+    /// Construct an identifier, replacing any characters SPDX doesn't allow in one with '-'.
+    ///
+    /// Only letters, digits, '.', and '-' are allowed.
+    pub fn new(id: &str) -> Self {
+        SpdxRef(
+            id.chars()
+                .map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+                    _ => '-',
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Make identifiers unique, appending a counter to any after the first which are the same.
+///
+/// The counter is skipped past any identifier already taken, so it never produces another
+/// one of the identifiers.
+pub fn unique_refs(bases: Vec<SpdxRef>) -> Vec<SpdxRef> {
+    let mut taken = bases.iter().cloned().collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+
+    bases
+        .into_iter()
+        .map(|base| {
+            let id = if seen.insert(base.clone()) {
+                base
+            } else {
+                (2..)
+                    .map(|n| SpdxRef(format!("{}-{}", base.0, n)))
+                    .find(|id| taken.contains(id).not())
+                    // PANIC SAFETY: There are only finitely many identifiers taken.
+                    .unwrap()
+            };
+
+            taken.insert(id.clone());
+            id
+        })
+        .collect()
+}
+
+/// The type and namespace given to the package URLs of packages.
+#[derive(Debug, Clone, Copy)]
+pub struct PurlPrefix<'a> {
+    /// The package URL type, like 'cargo'.
+    pub purl_type: &'a str,
+    /// The package URL namespace, if any.
+    pub namespace: Option<&'a str>,
+}
+
+impl<'a> PurlPrefix<'a> {
+    /// Get the package URL type and namespace selected by the user.
+    pub fn from_args(args: &'a GenerateOptions) -> Self {
+        PurlPrefix {
+            purl_type: args.purl_type(),
+            namespace: args.purl_namespace(),
+        }
+    }
+}
+
+/// The identifiers of the packages from the crate metadata.
+#[derive(Debug)]
+pub struct PackageRefs(
+    /// The identifier of each package.
+    HashMap<PackageId, SpdxRef>,
+);
+
+impl PackageRefs {
+    /// Assign each package an identifier from its name and version.
+    ///
+    /// Distinct packages whose identifiers would be the same, like 'foo_bar' and 'foo-bar',
+    /// versions differing only in build metadata, like '1.2.3+a' and '1.2.3-a', or the same
+    /// version of a crate from two sources, are told apart by appending a counter. The counter
+    /// is skipped past any identifier already taken, like that of version '1.2.3-a-2'.
+    /// Packages are numbered in the order of their IDs, so the assignment is stable.
+    pub fn new(metadata: &CrateMetadata) -> Self {
+        let mut packages = metadata.packages().collect::<Vec<_>>();
+        packages.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let ids = unique_refs(
+            packages
+                .iter()
+                .map(|package| SpdxRef::new(&format!("{}-{}", package.name, package.version)))
+                .collect(),
+        );
+
+        PackageRefs(
+            packages
+                .into_iter()
+                .map(|package| package.id.clone())
+                .zip(ids)
+                .collect(),
+        )
+    }
+
+    /// Get the identifier of a package from the crate metadata.
+    pub fn get(&self, package: &Package) -> SpdxRef {
+        self.0
+            .get(&package.id)
+            .cloned()
+            .unwrap_or_else(|| SpdxRef::new(&format!("{}-{}", package.name, package.version)))
+    }
+}
+
+/// A field value which may instead be marked as undetermined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpdxValue<T> {
+    /// The value couldn't be determined, or no attempt was made to determine it.
+    NoAssertion,
+    /// The determined value.
+    Value(T),
+}
+
+impl<T> Default for SpdxValue<T> {
+    fn default() -> Self {
+        SpdxValue::NoAssertion
+    }
+}
+
+impl<T: Display> Display for SpdxValue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpdxValue::NoAssertion => write!(f, "NOASSERTION"),
+            SpdxValue::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// An SPDX license expression.
+#[derive(Debug, Display, Clone, PartialEq, Eq, From)]
+pub struct LicenseExpression(pub String);
+
+/// A reference to the package in an external system.
+#[derive(Debug, Display, Clone)]
+#[display(fmt = "{} {} {}", category, reference_type, locator)]
+pub struct ExternalReference {
+    /// The category of the external system.
+    pub category: ReferenceCategory,
+    /// The type of reference within the category.
+    pub reference_type: String,
+    /// The locator of the package within the external system.
+    pub locator: String,
+    /// A freeform comment about the reference.
+    pub comment: Option<String>,
+}
+
+impl ExternalReference {
+    /// Construct a new reference to a security advisory.
+    pub fn advisory(locator: String) -> Self {
+        ExternalReference {
+            category: ReferenceCategory::Security,
+            reference_type: String::from("advisory"),
+            locator,
+            comment: None,
+        }
+    }
+
+    /// Construct a new reference to a version control repository.
+    pub fn vcs(locator: String) -> Self {
+        ExternalReference {
+            category: ReferenceCategory::Other,
+            reference_type: String::from("vcs"),
+            locator,
+            comment: None,
+        }
+    }
+
+    /// Construct a new package URL reference.
+    pub fn purl(locator: String) -> Self {
+        ExternalReference {
+            category: ReferenceCategory::PackageManager,
+            reference_type: String::from("purl"),
+            locator,
+            comment: None,
+        }
+    }
+}
+
+/// The category of an external reference.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReferenceCategory {
+    /// A security system, like an advisory database.
+    #[display(fmt = "SECURITY")]
+    Security,
+    /// A package manager, like crates.io.
+    #[display(fmt = "PACKAGE-MANAGER")]
+    PackageManager,
+    /// Any other system, like a version control repository.
+    #[display(fmt = "OTHER")]
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{package, package_json, MetadataFixture, TempDir};
+    use std::ops::Not as _;
+    use std::str::FromStr;
+
+    /// Build the information of a package, with the default options.
+    fn information(
+        package: &Package,
+        other_licenses: &mut Vec<OtherLicensingInformation>,
+    ) -> PackageInformation {
+        PackageInformation::from_metadata_package(
+            package,
+            SpdxRef::new("fixture-0.1.0"),
+            Path::new("/"),
+            other_licenses,
+            false,
+            false,
+            PurlPrefix {
+                purl_type: "cargo",
+                namespace: None,
+            },
+        )
+    }
+
+    /// Build the information of a package from the given source.
+    fn information_from(source: Option<&str>) -> PackageInformation {
+        let dir = TempDir::new();
+
+        let mut json = package_json(dir.path(), "fixture", "0.1.0");
+        json["source"] = source.into();
+
+        information(&package(json), &mut vec![])
+    }
+
+    /// Get the package URL of a package.
+    fn purl_of(info: &PackageInformation) -> &ExternalReference {
+        // PANIC SAFETY: Every package has a package URL.
+        info.external_reference
+            .iter()
+            .find(|reference| reference.reference_type == "purl")
+            .unwrap()
+    }
+
+    #[test]
+    fn registry_packages_have_plain_purls() {
+        let info = information_from(Some(
+            "registry+https://github.com/rust-lang/crates.io-index",
+        ));
+
+        let purl = purl_of(&info);
+        assert_eq!(purl.locator, "pkg:cargo/fixture@0.1.0");
+        assert_eq!(purl.comment, None);
+    }
+
+    #[test]
+    fn git_packages_have_vcs_url_qualifiers() {
+        let info = information_from(Some("git+https://github.com/org/repo#0123abcd"));
+
+        let purl = purl_of(&info);
+        let parsed = PackageUrl::from_str(&purl.locator).unwrap();
+        assert_eq!(parsed.name(), "fixture");
+        assert_eq!(parsed.version(), Some("0.1.0"));
+        assert_eq!(
+            parsed.qualifiers().get("vcs_url").map(|url| url.as_ref()),
+            Some("git+https://github.com/org/repo@0123abcd")
+        );
+    }
+
+    #[test]
+    fn git_branch_packages_have_encoded_vcs_url_qualifiers() {
+        let info = information_from(Some(
+            "git+https://github.com/org/repo?branch=feature/purl+encoding#0123abcd",
+        ));
+
+        // The requested branch is replaced by the revision it resolved to, and the URL is
+        // encoded so the qualifier survives being parsed back out of the purl.
+        let purl = purl_of(&info);
+        assert!(purl.locator.contains("branch").not(), "{}", purl.locator);
+
+        let parsed = PackageUrl::from_str(&purl.locator).unwrap();
+        assert_eq!(parsed.name(), "fixture");
+        assert_eq!(parsed.version(), Some("0.1.0"));
+        assert_eq!(
+            parsed.qualifiers().get("vcs_url").map(|url| url.as_ref()),
+            Some("git+https://github.com/org/repo@0123abcd")
+        );
+    }
+
+    #[test]
+    fn build_metadata_is_kept_in_purls() {
+        let dir = TempDir::new();
+
+        let mut json = package_json(dir.path(), "fixture", "1.2.3+build.5");
+        json["source"] = "registry+https://github.com/rust-lang/crates.io-index".into();
+
+        let info = information(&package(json), &mut vec![]);
+        let purl = purl_of(&info);
+        let parsed = PackageUrl::from_str(&purl.locator).unwrap();
+
+        assert_eq!(info.package_version.as_deref(), Some("1.2.3+build.5"));
+        assert_eq!(parsed.version(), Some("1.2.3+build.5"));
+    }
+
+    #[test]
+    fn external_references_are_sorted_whatever_order_they_were_found_in() {
+        let references = vec![
+            ExternalReference::vcs(String::from("https://github.com/org/repo")),
+            ExternalReference::advisory(String::from("https://rustsec.org/RUSTSEC-2022-0002")),
+            ExternalReference::purl(String::from("pkg:cargo/fixture@0.1.0")),
+            ExternalReference::advisory(String::from("https://rustsec.org/RUSTSEC-2022-0001")),
+        ];
+
+        let sorted = |references: Vec<ExternalReference>| {
+            let mut info = information_from(None);
+            info.external_reference = references;
+            info.sort_external_references();
+            info.external_reference
+                .into_iter()
+                .map(|reference| reference.locator)
+                .collect::<Vec<_>>()
+        };
+
+        let expected = [
+            "https://rustsec.org/RUSTSEC-2022-0001",
+            "https://rustsec.org/RUSTSEC-2022-0002",
+            "pkg:cargo/fixture@0.1.0",
+            "https://github.com/org/repo",
+        ];
+        assert_eq!(sorted(references.clone()), expected);
+        assert_eq!(sorted(references.into_iter().rev().collect()), expected);
+    }
+
+    #[test]
+    fn path_packages_note_their_path() {
+        let info = information_from(None);
+
+        let purl = purl_of(&info);
+        assert_eq!(purl.locator, "pkg:cargo/fixture@0.1.0");
+        assert!(purl
+            .comment
+            .as_deref()
+            .unwrap()
+            .starts_with("Local path dependency at"));
+    }
+
+    #[test]
+    fn license_file_is_recorded_as_other_licensing_information() {
+        let dir = TempDir::new();
+        dir.write("fixture/LICENSE.txt", "All rights reserved.");
+
+        let mut json = package_json(dir.path(), "fixture", "0.1.0");
+        json["license_file"] = "LICENSE.txt".into();
+
+        let mut other_licenses = vec![];
+        let info = information(&package(json), &mut other_licenses);
+
+        assert_eq!(other_licenses.len(), 1);
+        let identifier = &other_licenses[0].license_identifier;
+        assert_eq!(identifier, "LicenseRef-fixture-0.1.0");
+        assert_eq!(other_licenses[0].extracted_text, "All rights reserved.");
+        assert_eq!(
+            info.declared_license,
+            SpdxValue::Value(LicenseExpression(identifier.clone()))
+        );
+        assert!(info
+            .comments_on_license
+            .unwrap()
+            .contains(identifier.as_str()));
+    }
+
+    #[test]
+    fn missing_license_file_makes_no_assertion() {
+        let dir = TempDir::new();
+
+        let mut json = package_json(dir.path(), "fixture", "0.1.0");
+        json["license_file"] = "LICENSE.txt".into();
+
+        let mut other_licenses = vec![];
+        let info = information(&package(json), &mut other_licenses);
+
+        assert!(other_licenses.is_empty());
+        assert_eq!(info.declared_license, SpdxValue::NoAssertion);
+    }
+
+    #[test]
+    fn underscores_are_replaced_in_identifiers() {
+        assert_eq!(
+            SpdxRef::new("foo_bar-1.0.0").to_string(),
+            "SPDXRef-foo-bar-1.0.0"
+        );
+    }
+
+    #[test]
+    fn identifiers_made_the_same_by_underscores_are_numbered() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let underscored = fixture.package("foo_bar", "1.0.0");
+        let hyphenated = fixture.package("foo-bar", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &underscored);
+        fixture.depend(&root, &hyphenated);
+
+        let (_, metadata) = fixture.load(&[]);
+        let refs = PackageRefs::new(&metadata);
+        let id_of = |name: &str| {
+            let package = metadata.packages().find(|p| p.name == name).unwrap();
+            refs.get(package).to_string()
+        };
+
+        // Packages are numbered in the order of their IDs, which sorts '-' before '_'.
+        assert_eq!(id_of("foo-bar"), "SPDXRef-foo-bar-1.0.0");
+        assert_eq!(id_of("foo_bar"), "SPDXRef-foo-bar-1.0.0-2");
+    }
+
+    #[test]
+    fn build_metadata_is_sanitized_in_identifiers() {
+        assert_eq!(
+            SpdxRef::new("fixture-1.2.3+build.5").to_string(),
+            "SPDXRef-fixture-1.2.3-build.5"
+        );
+    }
+
+    #[test]
+    fn identifiers_made_the_same_by_build_metadata_are_numbered() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        fixture.member(&root);
+
+        for version in ["1.2.3+a", "1.2.3-a", "1.2.3-a-2"] {
+            let id = fixture.package("foo", version);
+            fixture.depend(&root, &id);
+        }
+
+        let (_, metadata) = fixture.load(&[]);
+        let refs = PackageRefs::new(&metadata);
+        let id_of = |version: &str| {
+            let package = metadata
+                .packages()
+                .find(|p| p.version.to_string() == version)
+                .unwrap();
+            refs.get(package).to_string()
+        };
+
+        // '+' sorts before '-', and the counter skips the identifier of version '1.2.3-a-2'.
+        assert_eq!(id_of("1.2.3+a"), "SPDXRef-foo-1.2.3-a");
+        assert_eq!(id_of("1.2.3-a"), "SPDXRef-foo-1.2.3-a-3");
+        assert_eq!(id_of("1.2.3-a-2"), "SPDXRef-foo-1.2.3-a-2");
+    }
+}
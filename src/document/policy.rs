@@ -0,0 +1,145 @@
+//! Checks declared licenses against a policy of allowed and denied licenses.
+
+use anyhow::{anyhow, Result};
+use spdx::{Expression, LicenseReq};
+use std::ops::Not as _;
+
+/// The licenses packages may and may not be under.
+#[derive(Debug)]
+pub struct LicensePolicy {
+    /// The licenses packages may be under, or empty if any license which isn't denied is.
+    allowed: Vec<String>,
+    /// The licenses packages may not be under.
+    denied: Vec<String>,
+}
+
+impl LicensePolicy {
+    /// Construct a policy from lists of allowed and denied licenses.
+    ///
+    /// Each entry is a license identifier, optionally with an exception, or an expression
+    /// which allows or denies every license it names.
+    pub fn new(allowed: &[String], denied: &[String]) -> Result<Self> {
+        Ok(LicensePolicy {
+            allowed: requirements(allowed)?,
+            denied: requirements(denied)?,
+        })
+    }
+
+    /// Check whether a license expression is permitted.
+    ///
+    /// The expression is evaluated as a whole, so `A OR B` is permitted if either `A` or `B`
+    /// is, while `A AND B` is only permitted if both are. Expressions which can't be parsed
+    /// aren't permitted.
+    pub fn permits(&self, expression: &str) -> bool {
+        Expression::parse(expression).map_or(false, |expression| {
+            expression.evaluate(|req| self.permits_req(req))
+        })
+    }
+
+    /// Check whether a package whose license is unknown is permitted, which it is only if
+    /// there's no list of allowed licenses it would have to be on.
+    pub fn permits_unknown(&self) -> bool {
+        self.allowed.is_empty()
+    }
+
+    /// Check whether a single license, with any exception, is permitted.
+    ///
+    /// A policy entry without an exception covers the license with any exception.
+    fn permits_req(&self, req: &LicenseReq) -> bool {
+        let full = req.to_string();
+        let license = req.license.to_string();
+        let matches = |entry: &String| *entry == full || *entry == license;
+
+        self.denied.iter().any(matches).not()
+            && (self.allowed.is_empty() || self.allowed.iter().any(matches))
+    }
+}
+
+/// Split policy entries into the licenses, with any exceptions, they name.
+fn requirements(entries: &[String]) -> Result<Vec<String>> {
+    let mut requirements = vec![];
+
+    for entry in entries {
+        let expression = Expression::parse(entry)
+            .map_err(|e| anyhow!("invalid license '{}' in the policy: {}", entry, e))?;
+
+        requirements.extend(expression.requirements().map(|req| req.req.to_string()));
+    }
+
+    Ok(requirements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Make a policy from lists of allowed and denied licenses.
+    fn policy(allowed: &[&str], denied: &[&str]) -> LicensePolicy {
+        let strings = |entries: &[&str]| entries.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        LicensePolicy::new(&strings(allowed), &strings(denied)).unwrap()
+    }
+
+    #[test]
+    fn either_alternative_may_be_allowed() {
+        let policy = policy(&["MIT"], &[]);
+
+        assert!(policy.permits("MIT OR Apache-2.0"));
+        assert!(policy.permits("Apache-2.0 OR MIT"));
+        assert!(policy.permits("Apache-2.0").not());
+    }
+
+    #[test]
+    fn every_conjunct_must_be_permitted() {
+        let policy = policy(&[], &["GPL-3.0-only"]);
+
+        assert!(policy.permits("MIT AND GPL-3.0-only").not());
+        assert!(policy.permits("MIT AND Apache-2.0"));
+        assert!(policy.permits("MIT OR GPL-3.0-only"));
+    }
+
+    #[test]
+    fn bare_deny_entries_cover_every_exception() {
+        let policy = policy(&[], &["GPL-2.0"]);
+
+        assert!(policy.permits("GPL-2.0 WITH Classpath-exception-2.0").not());
+        assert!(policy.permits("GPL-2.0").not());
+    }
+
+    #[test]
+    fn qualified_deny_entries_only_cover_their_exception() {
+        let policy = policy(&[], &["GPL-2.0 WITH Classpath-exception-2.0"]);
+
+        assert!(policy.permits("GPL-2.0 WITH Classpath-exception-2.0").not());
+        assert!(policy.permits("GPL-2.0"));
+    }
+
+    #[test]
+    fn empty_allowlist_permits_anything_not_denied() {
+        let policy = policy(&[], &[]);
+
+        assert!(policy.permits("MIT"));
+        assert!(policy.permits("GPL-3.0-only AND BSD-3-Clause"));
+        assert!(policy.permits_unknown());
+    }
+
+    #[test]
+    fn allowlist_forbids_unknown_licenses() {
+        assert!(policy(&["MIT"], &[]).permits_unknown().not());
+        assert!(policy(&[], &["MIT"]).permits_unknown());
+    }
+
+    #[test]
+    fn unparseable_expressions_are_not_permitted() {
+        let policy = policy(&[], &[]);
+
+        assert!(policy.permits("MIT OR").not());
+        assert!(policy.permits("").not());
+    }
+
+    #[test]
+    fn unparseable_policy_entries_are_errors() {
+        let error = LicensePolicy::new(&[String::from("MIT AND")], &[]).unwrap_err();
+
+        assert!(error.to_string().contains("invalid license 'MIT AND'"));
+    }
+}
@@ -0,0 +1,55 @@
+//! Defines the relationships section of the SPDX document.
+
+use crate::document::package::SpdxRef;
+use clap::ArgEnum;
+use derive_more::Display;
+
+/// A relationship between two elements of the SPDX document.
+#[derive(Debug, Display, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[display(
+    fmt = "{} {} {}",
+    spdx_element_id,
+    relationship_type,
+    related_spdx_element
+)]
+pub struct Relationship {
+    /// The element the relationship is from.
+    pub spdx_element_id: SpdxRef,
+    /// The type of the relationship.
+    pub relationship_type: RelationshipType,
+    /// The element the relationship is to.
+    pub related_spdx_element: SpdxRef,
+}
+
+impl Relationship {
+    /// Construct a new relationship.
+    pub fn new(
+        spdx_element_id: SpdxRef,
+        relationship_type: RelationshipType,
+        related_spdx_element: SpdxRef,
+    ) -> Self {
+        Relationship {
+            spdx_element_id,
+            relationship_type,
+            related_spdx_element,
+        }
+    }
+}
+
+/// The type of a relationship between two elements.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+#[clap(rename_all = "snake_case")]
+pub enum RelationshipType {
+    /// The document describes the element.
+    #[display(fmt = "DESCRIBES")]
+    Describes,
+    /// The element depends on the related element.
+    #[display(fmt = "DEPENDS_ON")]
+    DependsOn,
+    /// The element contains the related element.
+    #[display(fmt = "CONTAINS")]
+    Contains,
+    /// The element is needed to build the related element, but isn't part of it.
+    #[display(fmt = "BUILD_DEPENDENCY_OF")]
+    BuildDependencyOf,
+}
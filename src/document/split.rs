@@ -0,0 +1,265 @@
+//! Splits a document into a smaller document for each package it has.
+
+use crate::document::package::{LicenseExpression, SpdxValue};
+use crate::document::{
+    Document, DocumentName, DocumentNamespace, PackageInformation, Relationship, RelationshipType,
+    SpdxRef,
+};
+use std::collections::HashSet;
+
+impl Document {
+    /// Split the document into one document per package, describing the package along with
+    /// its direct dependencies.
+    ///
+    /// Each document is named after the identifier of the package it describes, with
+    /// `extension` appended, and its namespace is this document's with the identifier added
+    /// to the path. Relationships, annotations, and licenses not on the SPDX license list are
+    /// kept where they concern what's left in the smaller document.
+    pub fn split_by_package(&self, extension: &str) -> Vec<Document> {
+        self.package_information
+            .iter()
+            .map(|package| self.package_document(package, extension))
+            .collect()
+    }
+
+    /// Build the document describing a single package and its direct dependencies.
+    fn package_document(&self, root: &PackageInformation, extension: &str) -> Document {
+        let id = &root.package_spdx_identifier;
+
+        // Direct dependencies either are depended on by the package, or are needed to build it.
+        let mut included = HashSet::from([id]);
+
+        for relationship in &self.relationships {
+            match relationship.relationship_type {
+                RelationshipType::DependsOn if &relationship.spdx_element_id == id => {
+                    included.insert(&relationship.related_spdx_element);
+                }
+                RelationshipType::BuildDependencyOf if &relationship.related_spdx_element == id => {
+                    included.insert(&relationship.spdx_element_id);
+                }
+                _ => {}
+            }
+        }
+
+        let packages = self
+            .package_information
+            .iter()
+            .filter(|package| included.contains(&package.package_spdx_identifier))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Relationships are kept when both their ends are still in the document.
+        let document = SpdxRef::document();
+        let mut elements = HashSet::from([&document]);
+
+        for package in &packages {
+            elements.insert(&package.package_spdx_identifier);
+            elements.extend(package.files.iter().map(|file| &file.file_spdx_identifier));
+        }
+
+        let mut relationships = vec![Relationship::new(
+            SpdxRef::document(),
+            RelationshipType::Describes,
+            id.clone(),
+        )];
+        relationships.extend(
+            self.relationships
+                .iter()
+                .filter(|relationship| {
+                    relationship.relationship_type != RelationshipType::Describes
+                })
+                .filter(|relationship| {
+                    elements.contains(&relationship.spdx_element_id)
+                        && elements.contains(&relationship.related_spdx_element)
+                })
+                .cloned(),
+        );
+
+        let annotations = self
+            .annotations
+            .iter()
+            .filter(|annotation| elements.contains(&annotation.spdx_identifier_reference))
+            .cloned()
+            .collect();
+
+        let licenses = packages.iter().flat_map(licenses_of).collect::<Vec<_>>();
+        let other_licensing_information_detected = self
+            .other_licensing_information_detected
+            .iter()
+            .filter(|license| {
+                licenses
+                    .iter()
+                    .any(|expression| names_license(expression, &license.license_identifier))
+            })
+            .cloned()
+            .collect();
+
+        let name = format!("{}{}", id, extension);
+
+        // The namespace of a document has no fragment, so it's always a base URL to extend.
+        let mut namespace = self.document_namespace.0.clone();
+
+        if let Ok(mut segments) = namespace.path_segments_mut() {
+            segments.pop_if_empty().push(&id.to_string());
+        }
+
+        Document {
+            spdx_version: self.spdx_version,
+            data_license: self.data_license.clone(),
+            spdx_identifier: self.spdx_identifier.clone(),
+            document_name: DocumentName(name),
+            document_namespace: DocumentNamespace(namespace),
+            external_document_references: self.external_document_references.clone(),
+            license_list_version: self.license_list_version.clone(),
+            creator: self.creator.clone(),
+            created: self.created.clone(),
+            creator_comment: self.creator_comment.clone(),
+            document_comment: self.document_comment.clone(),
+            package_information: packages,
+            other_licensing_information_detected,
+            relationships,
+            annotations,
+        }
+    }
+}
+
+/// Get every license expression given for a package or its files.
+fn licenses_of(package: &PackageInformation) -> Vec<&str> {
+    let mut licenses = Vec::new();
+    licenses.extend(expression(&package.declared_license));
+    licenses.extend(expression(&package.concluded_license));
+    licenses.extend(
+        package
+            .all_licenses_information_from_files
+            .iter()
+            .map(String::as_str),
+    );
+
+    for file in &package.files {
+        licenses.extend(expression(&file.concluded_license));
+        licenses.extend(file.license_information_in_file.iter().map(String::as_str));
+    }
+
+    licenses
+}
+
+/// Check whether a license expression names a license identifier.
+fn names_license(expression: &str, identifier: &str) -> bool {
+    expression
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .any(|token| token == identifier)
+}
+
+/// Get the license expression of a license field, if it asserts one.
+fn expression(value: &SpdxValue<LicenseExpression>) -> Option<&str> {
+    match value {
+        SpdxValue::Value(expression) => Some(expression.0.as_str()),
+        SpdxValue::NoAssertion => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MetadataFixture, TempDir};
+
+    /// Split a document where 'fixture' depends on 'a' and the proc-macro 'derive', and 'a'
+    /// depends on 'b'.
+    fn split() -> Vec<Document> {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let a = fixture.package("a", "1.0.0");
+        let b = fixture.package("b", "1.0.0");
+        let derive = fixture.package_with("derive", "1.0.0", |json| {
+            json["targets"][0]["kind"] = serde_json::json!(["proc-macro"]);
+        });
+        fixture.member(&root);
+        fixture.depend(&root, &a);
+        fixture.depend(&root, &derive);
+        fixture.depend(&a, &b);
+
+        fixture.build(&[]).split_by_package(".spdx.json")
+    }
+
+    /// Find the document describing a package.
+    fn document_of<'a>(docs: &'a [Document], id: &str) -> &'a Document {
+        let name = format!("{}.spdx.json", id);
+        docs.iter().find(|doc| doc.document_name.0 == name).unwrap()
+    }
+
+    /// Get the names of the packages in a document, sorted.
+    fn package_names(doc: &Document) -> Vec<&str> {
+        let mut names = doc
+            .package_information
+            .iter()
+            .map(|package| package.package_name.as_str())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    /// Get the relationships in a document, sorted.
+    fn relationships(doc: &Document) -> Vec<String> {
+        let mut relationships = doc
+            .relationships
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        relationships.sort();
+        relationships
+    }
+
+    #[test]
+    fn each_package_is_documented_with_its_direct_dependencies() {
+        let docs = split();
+        assert_eq!(docs.len(), 4);
+
+        let root = document_of(&docs, "SPDXRef-fixture-0.1.0");
+        assert_eq!(package_names(root), ["a", "derive", "fixture"]);
+
+        let a = document_of(&docs, "SPDXRef-a-1.0.0");
+        assert_eq!(package_names(a), ["a", "b"]);
+
+        let b = document_of(&docs, "SPDXRef-b-1.0.0");
+        assert_eq!(package_names(b), ["b"]);
+    }
+
+    #[test]
+    fn relationships_are_filtered_to_what_is_left() {
+        let docs = split();
+
+        let root = document_of(&docs, "SPDXRef-fixture-0.1.0");
+        assert_eq!(
+            relationships(root),
+            [
+                "SPDXRef-DOCUMENT DESCRIBES SPDXRef-fixture-0.1.0",
+                "SPDXRef-derive-1.0.0 BUILD_DEPENDENCY_OF SPDXRef-fixture-0.1.0",
+                "SPDXRef-fixture-0.1.0 DEPENDS_ON SPDXRef-a-1.0.0",
+                "SPDXRef-fixture-0.1.0 DEPENDS_ON SPDXRef-derive-1.0.0",
+            ]
+        );
+
+        // The package depending on 'a' isn't in its document, so neither is the relationship.
+        let a = document_of(&docs, "SPDXRef-a-1.0.0");
+        assert_eq!(
+            relationships(a),
+            [
+                "SPDXRef-DOCUMENT DESCRIBES SPDXRef-a-1.0.0",
+                "SPDXRef-a-1.0.0 DEPENDS_ON SPDXRef-b-1.0.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn namespaces_name_the_package() {
+        let docs = split();
+        let root = document_of(&docs, "SPDXRef-fixture-0.1.0");
+
+        assert!(root
+            .document_namespace
+            .0
+            .as_str()
+            .ends_with("/SPDXRef-fixture-0.1.0"));
+    }
+}
@@ -0,0 +1,39 @@
+//! Categorizes failures, so that each category exits with its own code.
+
+use derive_more::Display;
+
+/// The category of a failure, attached to errors as context.
+///
+/// Failures outside of any category exit with code 1.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The CLI arguments or configuration files are invalid. Exits with code 2.
+    #[display(fmt = "invalid arguments or configuration")]
+    Usage,
+    /// The crate metadata couldn't be resolved. Exits with code 3.
+    #[display(fmt = "failed to resolve the crate metadata")]
+    Metadata,
+    /// The SBOM couldn't be written. Exits with code 4.
+    #[display(fmt = "failed to write the SBOM")]
+    Io,
+    /// The SBOM failed a check it was required to pass. Exits with code 5.
+    #[display(fmt = "the SBOM failed validation")]
+    Validation,
+}
+
+impl ErrorKind {
+    /// Get the category of an error, if it has one.
+    pub fn of(error: &anyhow::Error) -> Option<Self> {
+        error.downcast_ref::<ErrorKind>().copied()
+    }
+
+    /// Get the code the process exits with for failures in this category.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::Usage => 2,
+            ErrorKind::Metadata => 3,
+            ErrorKind::Io => 4,
+            ErrorKind::Validation => 5,
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Writes the SPDX 2.x JSON format out.
+
+use crate::document::{
+    Annotation, Checksum, Document, FileInformation, OtherLicensingInformation, PackageInformation,
+    Relationship, SpdxRef, SpdxVersion,
+};
+use crate::format::JsonStyle;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::ops::Not as _;
+
+/// Write the document out to the provided writer.
+///
+/// Object keys are always written in sorted order, so identical documents are written out
+/// byte-for-byte identically.
+pub fn write<W: Write>(w: W, doc: &Document, style: JsonStyle) -> Result<()> {
+    write_value(w, &to_value(doc), style)
+}
+
+/// Write the object translated from a document out to the provided writer.
+pub fn write_value<W: Write>(w: W, object: &Value, style: JsonStyle) -> Result<()> {
+    log::info!(target: "cargo_spdx", "writing out file in JSON format");
+
+    match style {
+        JsonStyle::Pretty => serde_json::to_writer_pretty(w, object)?,
+        JsonStyle::Compact => serde_json::to_writer(w, object)?,
+    }
+
+    Ok(())
+}
+
+/// Translate the document into the object written out as JSON.
+///
+/// The YAML format is the same object, written out as YAML.
+pub fn to_value(doc: &Document) -> Value {
+    let packages = doc
+        .package_information
+        .iter()
+        .map(|package| package_object(package, &doc.annotations, doc.spdx_version))
+        .collect::<Vec<_>>();
+
+    let files = doc
+        .package_information
+        .iter()
+        .flat_map(|package| &package.files)
+        .map(|file| file_object(file, doc.spdx_version))
+        .collect::<Vec<_>>();
+
+    let relationships = doc
+        .relationships
+        .iter()
+        .map(relationship_object)
+        .collect::<Vec<_>>();
+
+    let mut object = header_object(doc);
+    object["packages"] = Value::Array(packages);
+    object["relationships"] = Value::Array(relationships);
+    insert_all(&mut object, "files", files);
+
+    object
+}
+
+/// Translate everything about the document except its packages, files, and relationships
+/// into a JSON object.
+pub fn header_object(doc: &Document) -> Value {
+    let mut creation_info = json!({
+        "created": doc.created.to_string(),
+        "creators": doc.creator.iter().map(ToString::to_string).collect::<Vec<_>>(),
+    });
+
+    insert_opt(
+        &mut creation_info,
+        "licenseListVersion",
+        &doc.license_list_version,
+    );
+    insert_opt(&mut creation_info, "comment", &doc.creator_comment);
+
+    let external_document_refs = doc
+        .external_document_references
+        .iter()
+        .map(|reference| {
+            json!({
+                "externalDocumentId": format!("DocumentRef-{}", reference.id_string),
+                "spdxDocument": reference.document_uri.to_string(),
+                "checksum": checksum_object(&reference.checksum),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let extracted_licensing_infos = doc
+        .other_licensing_information_detected
+        .iter()
+        .map(other_license_object)
+        .collect::<Vec<_>>();
+
+    let mut object = json!({
+        "spdxVersion": doc.spdx_version.to_string(),
+        "dataLicense": doc.data_license.to_string(),
+        "SPDXID": doc.spdx_identifier.to_string(),
+        "name": doc.document_name.to_string(),
+        "documentNamespace": doc.document_namespace.to_string(),
+        "creationInfo": creation_info,
+    });
+
+    let annotations = doc
+        .annotations
+        .iter()
+        .filter(|annotation| annotation.spdx_identifier_reference == SpdxRef::document())
+        .map(annotation_object)
+        .collect::<Vec<_>>();
+
+    insert_opt(&mut object, "comment", &doc.document_comment);
+    insert_all(&mut object, "annotations", annotations);
+    insert_all(&mut object, "externalDocumentRefs", external_document_refs);
+    insert_all(
+        &mut object,
+        "hasExtractedLicensingInfos",
+        extracted_licensing_infos,
+    );
+
+    object
+}
+
+/// Translate a package into a JSON object, along with the annotations about it.
+pub fn package_object(
+    package: &PackageInformation,
+    annotations: &[Annotation],
+    version: SpdxVersion,
+) -> Value {
+    let mut object = json!({
+        "name": package.package_name,
+        "SPDXID": package.package_spdx_identifier.to_string(),
+        "downloadLocation": package.package_download_location.to_string(),
+        "filesAnalyzed": package.files_analyzed,
+        "licenseConcluded": package.concluded_license.to_string(),
+        "licenseDeclared": package.declared_license.to_string(),
+        "copyrightText": package.copyright_text.to_string(),
+    });
+
+    insert_opt(&mut object, "versionInfo", &package.package_version);
+    insert_opt(&mut object, "packageFileName", &package.package_file_name);
+    insert_opt(&mut object, "originator", &package.package_originator);
+    insert_opt(&mut object, "sourceInfo", &package.source_information);
+    insert_opt(&mut object, "comment", &package.package_comment);
+    insert_opt(&mut object, "licenseComments", &package.comments_on_license);
+    insert_opt(&mut object, "summary", &package.package_summary_description);
+    insert_opt(
+        &mut object,
+        "description",
+        &package.package_detailed_description,
+    );
+
+    if let Some(code) = &package.package_verification_code {
+        object["packageVerificationCode"] = json!({
+            "packageVerificationCodeValue": code.value,
+            "packageVerificationCodeExcludedFiles": code.excluded_files,
+        });
+    }
+
+    if package.all_licenses_information_from_files.is_empty().not() {
+        object["licenseInfoFromFiles"] = json!(package.all_licenses_information_from_files);
+    } else if package.files_analyzed && version.requires_license_info_from_files() {
+        object["licenseInfoFromFiles"] = json!(["NOASSERTION"]);
+    }
+
+    let external_refs = package
+        .external_reference
+        .iter()
+        .map(|reference| {
+            let mut object = json!({
+                "referenceCategory": reference.category.to_string(),
+                "referenceType": reference.reference_type,
+                "referenceLocator": reference.locator,
+            });
+
+            insert_opt(&mut object, "comment", &reference.comment);
+            object
+        })
+        .collect::<Vec<_>>();
+
+    let checksums = package
+        .package_checksum
+        .iter()
+        .map(checksum_object)
+        .collect::<Vec<_>>();
+
+    let has_files = package
+        .files
+        .iter()
+        .map(|file| json!(file.file_spdx_identifier.to_string()))
+        .collect::<Vec<_>>();
+
+    let annotations = annotations
+        .iter()
+        .filter(|annotation| {
+            annotation.spdx_identifier_reference == package.package_spdx_identifier
+        })
+        .map(annotation_object)
+        .collect::<Vec<_>>();
+
+    let attribution_texts = package
+        .package_attribution_text
+        .iter()
+        .map(|text| json!(text))
+        .collect::<Vec<_>>();
+
+    insert_all(&mut object, "checksums", checksums);
+    insert_all(&mut object, "attributionTexts", attribution_texts);
+    insert_all(&mut object, "externalRefs", external_refs);
+    insert_all(&mut object, "hasFiles", has_files);
+    insert_all(&mut object, "annotations", annotations);
+    object
+}
+
+/// Translate a file into a JSON object.
+pub fn file_object(file: &FileInformation, version: SpdxVersion) -> Value {
+    let checksums = file
+        .file_checksum
+        .iter()
+        .map(checksum_object)
+        .collect::<Vec<_>>();
+
+    let mut object = json!({
+        "fileName": file.file_name,
+        "SPDXID": file.file_spdx_identifier.to_string(),
+        "fileTypes": file.file_types.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "checksums": checksums,
+        "licenseConcluded": file.concluded_license.to_string(),
+        "copyrightText": file.copyright_text.to_string(),
+    });
+
+    if file.license_information_in_file.is_empty().not() {
+        object["licenseInfoInFiles"] = json!(file.license_information_in_file);
+    } else if version.requires_license_info_from_files() {
+        object["licenseInfoInFiles"] = json!(["NOASSERTION"]);
+    }
+
+    if file.file_contributor.is_empty().not() {
+        object["fileContributors"] = json!(file.file_contributor);
+    }
+
+    object
+}
+
+/// Translate a checksum into a JSON object.
+fn checksum_object(checksum: &Checksum) -> Value {
+    json!({
+        "algorithm": checksum.algorithm.to_string(),
+        "checksumValue": checksum.value,
+    })
+}
+
+/// Translate a license which isn't on the SPDX license list into a JSON object.
+fn other_license_object(other_license: &OtherLicensingInformation) -> Value {
+    let mut object = json!({
+        "licenseId": other_license.license_identifier,
+        "extractedText": other_license.extracted_text,
+    });
+
+    insert_opt(&mut object, "comment", &other_license.license_comment);
+    object
+}
+
+/// Translate a relationship into a JSON object.
+pub fn relationship_object(relationship: &Relationship) -> Value {
+    json!({
+        "spdxElementId": relationship.spdx_element_id.to_string(),
+        "relationshipType": relationship.relationship_type.to_string(),
+        "relatedSpdxElement": relationship.related_spdx_element.to_string(),
+    })
+}
+
+/// Translate an annotation into a JSON object.
+fn annotation_object(annotation: &Annotation) -> Value {
+    json!({
+        "annotator": annotation.annotator.to_string(),
+        "annotationDate": annotation.annotation_date.to_string(),
+        "annotationType": annotation.annotation_type.to_string(),
+        "comment": annotation.annotation_comment,
+    })
+}
+
+/// Insert an optional field into an object, leaving it out if it's missing.
+fn insert_opt<T: ToString>(object: &mut Value, key: &str, value: &Option<T>) {
+    if let (Some(object), Some(value)) = (object.as_object_mut(), value) {
+        object.insert(key.to_owned(), Value::String(value.to_string()));
+    }
+}
+
+/// Insert an array field into an object, leaving it out if it's empty.
+fn insert_all(object: &mut Value, key: &str, values: Vec<Value>) {
+    if values.is_empty() {
+        return;
+    }
+
+    if let Some(object) = object.as_object_mut() {
+        object.insert(key.to_owned(), Value::Array(values));
+    }
+}
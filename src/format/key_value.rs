@@ -1,8 +1,12 @@
 //! Writes the flat file format out.
 
-use crate::document::Document;
+use crate::document::{
+    Annotation, Document, FileInformation, OtherLicensingInformation, PackageInformation,
+    SpdxVersion,
+};
 use anyhow::Result;
 use std::io::Write;
+use std::ops::Not as _;
 
 /// Convenience macro to provide uniform field-writing syntax.
 ///
@@ -12,11 +16,20 @@ use std::io::Write;
 ///
 /// Making it easier to skim the code at the call-sites it intended to make
 /// the code more closely resemble the structure of the file being written out.
+///
+/// Values spanning several lines are wrapped in `<text>` tags, unless the field always is,
+/// so each line isn't read as a field of its own.
 macro_rules! write_field {
     // Write out a single field.
-    ( $f:ident, $fmt:literal, $field:expr ) => {
-        writeln!($f, $fmt, $field)?
-    };
+    ( $f:ident, $fmt:literal, $field:expr ) => {{
+        let value = $field.to_string();
+
+        if value.contains('\n') && $fmt.contains("<text>").not() {
+            writeln!($f, $fmt, format!("<text>{}</text>", value))?
+        } else {
+            writeln!($f, $fmt, value)?
+        }
+    }};
 
     // Write out an optional field.
     ( @opt, $f:ident, $fmt:literal, $field:expr ) => {
@@ -42,12 +55,200 @@ pub fn write<W: Write>(mut w: W, doc: &Document) -> Result<()> {
     write_field!(w, "SPDXID: {}", doc.spdx_identifier);
     write_field!(w, "DocumentName: {}", doc.document_name);
     write_field!(w, "DocumentNamespace: {}", doc.document_namespace);
-    write_field!(@opt, w, "ExternalDocumentRef: {}", doc.external_document_reference);
+    write_field!(@all, w, "ExternalDocumentRef: {}", doc.external_document_references);
     write_field!(@opt, w, "LicenseListVersion: {}", doc.license_list_version);
     write_field!(@all, w, "Creator: {}", doc.creator);
     write_field!(w, "Created: {}", doc.created);
-    write_field!(@opt, w, "CreatorComment: {}", doc.creator_comment);
-    write_field!(@opt, w, "DocumentComment: {}", doc.document_comment);
+    write_field!(@opt, w, "CreatorComment: <text>{}</text>", doc.creator_comment);
+    write_field!(@opt, w, "DocumentComment: <text>{}</text>", doc.document_comment);
+
+    for package in &doc.package_information {
+        writeln!(w)?;
+        write_package(&mut w, package, doc.spdx_version)?;
+
+        for file in &package.files {
+            writeln!(w)?;
+            write_file(&mut w, file, doc.spdx_version)?;
+        }
+    }
+
+    for other_license in &doc.other_licensing_information_detected {
+        writeln!(w)?;
+        write_other_license(&mut w, other_license)?;
+    }
+
+    for annotation in &doc.annotations {
+        writeln!(w)?;
+        write_annotation(&mut w, annotation)?;
+    }
+
+    if doc.relationships.is_empty().not() {
+        writeln!(w)?;
+    }
+
+    write_field!(@all, w, "Relationship: {}", doc.relationships);
+
+    Ok(())
+}
+
+/// Write out the information for a single package.
+fn write_package<W: Write>(
+    w: &mut W,
+    package: &PackageInformation,
+    version: SpdxVersion,
+) -> Result<()> {
+    write_field!(w, "PackageName: {}", package.package_name);
+    write_field!(w, "SPDXID: {}", package.package_spdx_identifier);
+    write_field!(@opt, w, "PackageVersion: {}", package.package_version);
+    write_field!(@opt, w, "PackageFileName: {}", package.package_file_name);
+    write_field!(@opt, w, "PackageOriginator: {}", package.package_originator);
+    write_field!(
+        w,
+        "PackageDownloadLocation: {}",
+        package.package_download_location
+    );
+    write_field!(w, "FilesAnalyzed: {}", package.files_analyzed);
+    write_field!(@opt, w, "PackageVerificationCode: {}", package.package_verification_code);
+    write_field!(@all, w, "PackageChecksum: {}", package.package_checksum);
+    write_field!(@opt, w, "PackageSourceInfo: <text>{}</text>", package.source_information);
+    write_field!(w, "PackageLicenseConcluded: {}", package.concluded_license);
+
+    if package.all_licenses_information_from_files.is_empty().not() {
+        write_field!(@all, w, "PackageLicenseInfoFromFiles: {}", package.all_licenses_information_from_files);
+    } else if package.files_analyzed && version.requires_license_info_from_files() {
+        write_field!(w, "PackageLicenseInfoFromFiles: {}", "NOASSERTION");
+    }
+
+    write_field!(w, "PackageLicenseDeclared: {}", package.declared_license);
+    write_field!(@opt, w, "PackageLicenseComments: <text>{}</text>", package.comments_on_license);
+    write_field!(w, "PackageCopyrightText: {}", package.copyright_text);
+    write_field!(@opt, w, "PackageSummary: <text>{}</text>", package.package_summary_description);
+    write_field!(@opt, w, "PackageDescription: <text>{}</text>", package.package_detailed_description);
+
+    write_field!(@opt, w, "PackageComment: <text>{}</text>", package.package_comment);
+    write_field!(@all, w, "PackageAttributionText: <text>{}</text>", package.package_attribution_text);
+
+    for reference in &package.external_reference {
+        write_field!(w, "ExternalRef: {}", reference);
+        write_field!(@opt, w, "ExternalRefComment: <text>{}</text>", reference.comment);
+    }
+
+    Ok(())
+}
+
+/// Write out the information for a single file.
+fn write_file<W: Write>(w: &mut W, file: &FileInformation, version: SpdxVersion) -> Result<()> {
+    write_field!(w, "FileName: {}", file.file_name);
+    write_field!(w, "SPDXID: {}", file.file_spdx_identifier);
+    write_field!(@all, w, "FileType: {}", file.file_types);
+    write_field!(@all, w, "FileChecksum: {}", file.file_checksum);
+    write_field!(w, "LicenseConcluded: {}", file.concluded_license);
+
+    if file.license_information_in_file.is_empty().not() {
+        write_field!(@all, w, "LicenseInfoInFile: {}", file.license_information_in_file);
+    } else if version.requires_license_info_from_files() {
+        write_field!(w, "LicenseInfoInFile: {}", "NOASSERTION");
+    }
+
+    write_field!(w, "FileCopyrightText: {}", file.copyright_text);
+    write_field!(@all, w, "FileContributor: {}", file.file_contributor);
+
+    Ok(())
+}
+
+/// Write out a license which isn't on the SPDX license list.
+fn write_other_license<W: Write>(
+    w: &mut W,
+    other_license: &OtherLicensingInformation,
+) -> Result<()> {
+    write_field!(w, "LicenseID: {}", other_license.license_identifier);
+    write_field!(
+        w,
+        "ExtractedText: <text>{}</text>",
+        other_license.extracted_text
+    );
+    write_field!(@opt, w, "LicenseComment: {}", other_license.license_comment);
+
+    Ok(())
+}
+
+/// Write out an annotation.
+fn write_annotation<W: Write>(w: &mut W, annotation: &Annotation) -> Result<()> {
+    write_field!(w, "Annotator: {}", annotation.annotator);
+    write_field!(w, "AnnotationDate: {}", annotation.annotation_date);
+    write_field!(w, "AnnotationType: {}", annotation.annotation_type);
+    write_field!(w, "SPDXREF: {}", annotation.spdx_identifier_reference);
+    write_field!(
+        w,
+        "AnnotationComment: <text>{}</text>",
+        annotation.annotation_comment
+    );
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::SpdxValue;
+    use crate::test_support::{MetadataFixture, TempDir};
+
+    /// Write a fixture document out, after changing its root package.
+    fn write_with(edit: impl FnOnce(&mut PackageInformation)) -> String {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        fixture.member(&root);
+
+        let mut doc = fixture.build(&[]);
+        // PANIC SAFETY: The fixture has a single package.
+        edit(&mut doc.package_information[0]);
+
+        let mut output = Vec::new();
+        write(&mut output, &doc).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn multi_line_values_are_wrapped_in_text_tags() {
+        let output = write_with(|package| {
+            package.copyright_text =
+                SpdxValue::Value(String::from("Copyright 2022 First\nCopyright 2022 Second"));
+        });
+
+        assert!(
+            output.contains(
+                "PackageCopyrightText: <text>Copyright 2022 First\nCopyright 2022 Second</text>\n"
+            ),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn single_line_values_are_written_as_is() {
+        let output = write_with(|package| {
+            package.copyright_text = SpdxValue::Value(String::from("Copyright 2022 First"));
+        });
+
+        assert!(
+            output.contains("PackageCopyrightText: Copyright 2022 First\n"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn text_fields_are_wrapped_once() {
+        let output = write_with(|package| {
+            package.package_comment = Some(String::from("First line\nSecond line"));
+        });
+
+        assert!(
+            output.contains("PackageComment: <text>First line\nSecond line</text>\n"),
+            "{}",
+            output
+        );
+        assert!(output.contains("<text><text>").not(), "{}", output);
+    }
+}
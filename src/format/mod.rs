@@ -1,9 +1,15 @@
 //! Defines the output formats supported by `cargo-spdx`.
 
+pub mod json;
 pub mod key_value;
+pub mod ndjson;
+pub mod spdx3;
+pub mod yaml;
 
 use anyhow::{anyhow, Error};
+use clap::ArgEnum;
 use std::fmt::{Display, Formatter};
+use std::path::Path;
 use std::str::FromStr;
 
 /// The output format for the SPDX document.
@@ -17,6 +23,10 @@ pub enum Format {
     Yaml,
     /// RDF format.
     Rdf,
+    /// SPDX 3.0 JSON-LD format.
+    Spdx3,
+    /// Newline-delimited JSON, one record per line. Not an SPDX format.
+    Ndjson,
 }
 
 impl Format {
@@ -27,8 +37,36 @@ impl Format {
             Format::Json => ".spdx.json",
             Format::Yaml => ".spdx.yaml",
             Format::Rdf => ".spdx.rdf",
+            Format::Spdx3 => ".spdx3.json",
+            Format::Ndjson => ".spdx.ndjson",
         }
     }
+
+    /// Infer the format from the extension of a file path.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        // SPDX 3.0 documents are JSON too, so they're told apart by the full extension.
+        if path.to_str()?.ends_with(Format::Spdx3.extension()) {
+            return Some(Format::Spdx3);
+        }
+
+        match path.extension()?.to_str()? {
+            "spdx" => Some(Format::KeyValue),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "rdf" | "xml" => Some(Format::Rdf),
+            "ndjson" | "jsonl" => Some(Format::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+/// How JSON output is laid out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ArgEnum)]
+pub enum JsonStyle {
+    /// Indented across several lines, for reading and diffing.
+    Pretty,
+    /// On a single line, for storage and machine ingestion.
+    Compact,
 }
 
 impl Default for Format {
@@ -44,6 +82,8 @@ impl Display for Format {
             Format::Json => write!(f, "JSON"),
             Format::Yaml => write!(f, "YAML"),
             Format::Rdf => write!(f, "RDF"),
+            Format::Spdx3 => write!(f, "SPDX-3.0"),
+            Format::Ndjson => write!(f, "NDJSON"),
         }
     }
 }
@@ -57,6 +97,8 @@ impl FromStr for Format {
             "json" | "JSON" => Ok(Format::Json),
             "yaml" | "YAML" => Ok(Format::Yaml),
             "rdf" | "RDF" => Ok(Format::Rdf),
+            "spdx3" | "SPDX-3.0" => Ok(Format::Spdx3),
+            "ndjson" | "NDJSON" => Ok(Format::Ndjson),
             s => Err(anyhow!("unknown format '{}'", s)),
         }
     }
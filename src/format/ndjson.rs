@@ -0,0 +1,91 @@
+//! Writes the document out as newline-delimited JSON.
+//!
+//! This isn't an SPDX serialization, and no SPDX tool will read it. It's meant for internal
+//! pipelines processing very large documents record by record.
+//!
+//! The document is still built in memory in full before it's written out, so this doesn't
+//! bound memory use while generating it. It only avoids translating the whole document into
+//! one JSON object, so writing it out holds no more than one record at a time on top of the
+//! document.
+
+use crate::document::Document;
+use crate::format::json;
+use anyhow::Result;
+use serde_json::Value;
+use std::io::Write;
+
+/// Write the document out to the provided writer.
+///
+/// Each line is one record, with a `recordType` of "document", "package", "file", or
+/// "relationship". The document record comes first, holding everything but the other
+/// records, which are each translated and written out in turn rather than being collected
+/// into one object.
+pub fn write<W: Write>(mut w: W, doc: &Document) -> Result<()> {
+    log::info!(target: "cargo_spdx", "writing out file in NDJSON format");
+
+    write_record(&mut w, "document", json::header_object(doc))?;
+
+    for package in &doc.package_information {
+        let object = json::package_object(package, &doc.annotations, doc.spdx_version);
+        write_record(&mut w, "package", object)?;
+    }
+
+    for file in doc
+        .package_information
+        .iter()
+        .flat_map(|package| &package.files)
+    {
+        write_record(&mut w, "file", json::file_object(file, doc.spdx_version))?;
+    }
+
+    for relationship in &doc.relationships {
+        write_record(
+            &mut w,
+            "relationship",
+            json::relationship_object(relationship),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Write one record out on its own line, tagged with its type.
+fn write_record<W: Write>(w: &mut W, record_type: &str, mut object: Value) -> Result<()> {
+    object["recordType"] = Value::String(record_type.to_owned());
+    serde_json::to_writer(&mut *w, &object)?;
+    writeln!(w)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MetadataFixture, TempDir};
+
+    #[test]
+    fn each_line_is_one_record() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let dependency = fixture.package("dependency", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &dependency);
+
+        let doc = fixture.build(&[]);
+        let mut output = Vec::new();
+        write(&mut output, &doc).unwrap();
+
+        let record_types = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let record: Value = serde_json::from_str(line).unwrap();
+                record["recordType"].as_str().unwrap().to_owned()
+            })
+            .collect::<Vec<_>>();
+
+        let mut expected = vec!["document", "package", "package"];
+        expected.extend(doc.relationships.iter().map(|_| "relationship"));
+        assert_eq!(record_types, expected);
+    }
+}
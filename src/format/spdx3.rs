@@ -0,0 +1,204 @@
+//! Writes the SPDX 3.0 JSON-LD format out.
+//!
+//! The document is translated from the SPDX 2.2 model into a graph of typed elements.
+//! Packages, files, package URLs, file checksums, annotations, and the describes,
+//! depends-on, and contains relationships are mapped. Licenses, copyright text, other licensing
+//! information, external references other than package URLs, and comments aren't
+//! mapped yet, and are left out of the output.
+
+use crate::document::{
+    Creator, Document, FileInformation, PackageInformation, RelationshipType, SpdxValue,
+};
+use crate::format::JsonStyle;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::fmt::Display;
+use std::io::Write;
+
+/// The JSON-LD context for SPDX 3.0 documents.
+const CONTEXT: &str = "https://spdx.org/rdf/3.0.1/spdx-context.jsonld";
+
+/// The blank node identifier of the document's creation information.
+const CREATION_INFO: &str = "_:creationinfo";
+
+/// Write the document out to the provided writer.
+pub fn write<W: Write>(w: W, doc: &Document, style: JsonStyle) -> Result<()> {
+    log::info!(target: "cargo_spdx", "writing out file in SPDX 3.0 JSON-LD format");
+
+    let namespace = doc.document_namespace.to_string();
+    let id = |element: &dyn Display| format!("{}#{}", namespace, element);
+
+    let mut graph = vec![];
+    let mut created_by = vec![];
+    let mut created_using = vec![];
+
+    // Creators become agents and tools referenced from the creation information.
+    for (index, creator) in doc.creator.iter().enumerate() {
+        let creator_id = id(&format!("SPDXRef-Creator-{}", index));
+
+        let (element_type, name, email) = match creator {
+            Creator::Person { name, email } => ("Person", name, email.as_ref()),
+            Creator::Organization { name, email } => ("Organization", name, email.as_ref()),
+            Creator::Tool { name } => ("Tool", name, None),
+        };
+
+        let mut element = json!({
+            "type": element_type,
+            "spdxId": creator_id,
+            "creationInfo": CREATION_INFO,
+            "name": name,
+        });
+
+        if let Some(email) = email {
+            element["externalIdentifier"] = json!([{
+                "type": "ExternalIdentifier",
+                "externalIdentifierType": "email",
+                "identifier": email,
+            }]);
+        }
+
+        match creator {
+            Creator::Tool { .. } => created_using.push(creator_id),
+            _ => created_by.push(creator_id),
+        }
+
+        graph.push(element);
+    }
+
+    graph.insert(
+        0,
+        json!({
+            "type": "CreationInfo",
+            "@id": CREATION_INFO,
+            "specVersion": "3.0.1",
+            "created": doc.created.to_string(),
+            "createdBy": created_by,
+            "createdUsing": created_using,
+        }),
+    );
+
+    let mut elements = vec![];
+    let mut root_elements = vec![];
+
+    for package in &doc.package_information {
+        elements.push(id(&package.package_spdx_identifier));
+        graph.push(package_element(package, &id));
+
+        for file in &package.files {
+            elements.push(id(&file.file_spdx_identifier));
+            graph.push(file_element(file, &id));
+        }
+    }
+
+    for (index, relationship) in doc.relationships.iter().enumerate() {
+        let relationship_type = match relationship.relationship_type {
+            // The document describing an element is expressed by its root elements.
+            RelationshipType::Describes => {
+                root_elements.push(id(&relationship.related_spdx_element));
+                continue;
+            }
+            RelationshipType::DependsOn => "dependsOn",
+            RelationshipType::Contains => "contains",
+            // SPDX 3.0 has no equivalent, and the dependency is already recorded.
+            RelationshipType::BuildDependencyOf => continue,
+        };
+
+        let relationship_id = id(&format!("SPDXRef-Relationship-{}", index));
+        elements.push(relationship_id.clone());
+
+        graph.push(json!({
+            "type": "Relationship",
+            "spdxId": relationship_id,
+            "creationInfo": CREATION_INFO,
+            "from": id(&relationship.spdx_element_id),
+            "relationshipType": relationship_type,
+            "to": [id(&relationship.related_spdx_element)],
+        }));
+    }
+
+    for (index, annotation) in doc.annotations.iter().enumerate() {
+        let annotation_id = id(&format!("SPDXRef-Annotation-{}", index));
+        elements.push(annotation_id.clone());
+
+        graph.push(json!({
+            "type": "Annotation",
+            "spdxId": annotation_id,
+            "creationInfo": CREATION_INFO,
+            "annotationType": annotation.annotation_type.to_string().to_lowercase(),
+            "subject": id(&annotation.spdx_identifier_reference),
+            "statement": annotation.annotation_comment,
+        }));
+    }
+
+    graph.push(json!({
+        "type": "SpdxDocument",
+        "spdxId": id(&"SPDXRef-DOCUMENT"),
+        "creationInfo": CREATION_INFO,
+        "name": doc.document_name.to_string(),
+        "rootElement": root_elements,
+        "element": elements,
+    }));
+
+    let doc = json!({
+        "@context": CONTEXT,
+        "@graph": graph,
+    });
+
+    match style {
+        JsonStyle::Pretty => serde_json::to_writer_pretty(w, &doc)?,
+        JsonStyle::Compact => serde_json::to_writer(w, &doc)?,
+    }
+
+    Ok(())
+}
+
+/// Translate a package into a `software_Package` element.
+fn package_element(package: &PackageInformation, id: &dyn Fn(&dyn Display) -> String) -> Value {
+    let mut element = json!({
+        "type": "software_Package",
+        "spdxId": id(&package.package_spdx_identifier),
+        "creationInfo": CREATION_INFO,
+        "name": package.package_name,
+    });
+
+    if let Some(version) = &package.package_version {
+        element["software_packageVersion"] = json!(version);
+    }
+
+    if let SpdxValue::Value(location) = &package.package_download_location {
+        element["software_downloadLocation"] = json!(location);
+    }
+
+    if let Some(purl) = package
+        .external_reference
+        .iter()
+        .find(|reference| reference.reference_type == "purl")
+    {
+        element["software_packageUrl"] = json!(purl.locator);
+    }
+
+    element
+}
+
+/// Translate a file into a `software_File` element.
+fn file_element(file: &FileInformation, id: &dyn Fn(&dyn Display) -> String) -> Value {
+    let verified_using = file
+        .file_checksum
+        .iter()
+        .map(|checksum| {
+            json!({
+                "type": "Hash",
+                "algorithm": checksum.algorithm.to_string().to_lowercase(),
+                "hashValue": checksum.value,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "type": "software_File",
+        "spdxId": id(&file.file_spdx_identifier),
+        "creationInfo": CREATION_INFO,
+        "name": file.file_name,
+        "verifiedUsing": verified_using,
+    })
+}
@@ -0,0 +1,70 @@
+//! Writes the SPDX 2.x YAML format out.
+
+use crate::document::Document;
+use crate::format::json;
+use anyhow::Result;
+use serde_json::Value;
+use std::io::Write;
+
+/// Write the document out to the provided writer.
+///
+/// The document has the same structure as the JSON format. No anchors or aliases are ever
+/// written, since the serializer doesn't generate them, so the output is portable to any YAML
+/// parser. If `document_start` is set, the output begins with a `---` document start marker.
+pub fn write<W: Write>(w: W, doc: &Document, document_start: bool) -> Result<()> {
+    write_value(w, &json::to_value(doc), document_start)
+}
+
+/// Write the object translated from a document out to the provided writer.
+pub fn write_value<W: Write>(mut w: W, object: &Value, document_start: bool) -> Result<()> {
+    log::info!(target: "cargo_spdx", "writing out file in YAML format");
+
+    if document_start {
+        writeln!(w, "---")?;
+    }
+
+    serde_yaml::to_writer(w, object)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MetadataFixture, TempDir};
+    use std::ops::Not as _;
+
+    #[test]
+    fn repeated_structures_are_written_without_anchors() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        fixture.member(&root);
+
+        // Identical packages have identical sub-structures, which anchors could share.
+        for name in ["first", "second", "third"] {
+            let id = fixture.package_with(name, "1.0.0", |json| {
+                json["license"] = serde_json::json!("MIT");
+            });
+            fixture.depend(&root, &id);
+        }
+
+        let mut output = Vec::new();
+        write(&mut output, &fixture.build(&[]), true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("---\n"), "{}", output);
+
+        for line in output.lines() {
+            let value = line
+                .split_once(": ")
+                .map_or(line.trim_start(), |(_, value)| value);
+            let value = value.trim_start_matches("- ");
+
+            assert!(
+                value.starts_with('&').not() && value.starts_with('*').not(),
+                "anchor or alias in '{}'",
+                line
+            );
+        }
+    }
+}
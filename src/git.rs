@@ -1,27 +1,221 @@
 //! Functions for getting git metadata.
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use git2::build::CheckoutBuilder;
+use git2::{Config, Repository, Sort};
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
 
 /// Get the current Git user.
 ///
+/// The user is looked up in the configuration of the repository containing the
+/// current directory, then in the `GIT_AUTHOR_NAME` and `GIT_AUTHOR_EMAIL` environment
+/// variables, and finally in the global Git configuration.
+///
 /// This requires that the name is specified, but permits the
 /// email to be missing.
 pub fn get_current_user() -> Result<User> {
-    log::info!(target: "cargo_spdx", "loading default git configuration");
+    log::info!(target: "cargo_spdx", "loading git user");
 
-    let git_config = git2::Config::open_default()?.snapshot()?;
-    let name = git_config.get_str("user.name")?.to_owned();
-    let email = git_config.get_str("user.email").ok().map(ToOwned::to_owned);
+    let user = get_repo_user()
+        .or_else(|_| get_env_user())
+        .or_else(|_| get_default_user())?;
 
-    log::info!(target: "cargo_spdx", "detected git username: {}", name);
+    log::info!(target: "cargo_spdx", "detected git username: {}", user.name);
 
-    if let Some(email) = &email {
+    if let Some(email) = &user.email {
         log::info!(target: "cargo_spdx", "detected git email address: {}", email);
     }
 
+    Ok(user)
+}
+
+/// Get the user from the configuration of the repository containing the current directory.
+fn get_repo_user() -> Result<User> {
+    log::info!(target: "cargo_spdx", "loading repository git configuration");
+
+    let repo = Repository::discover(".")?;
+    let config = repo.config()?.snapshot()?;
+    get_config_user(&config)
+}
+
+/// Get the user from the Git author environment variables.
+fn get_env_user() -> Result<User> {
+    log::info!(target: "cargo_spdx", "loading git user from the environment");
+
+    let name = env::var("GIT_AUTHOR_NAME")?;
+    let email = env::var("GIT_AUTHOR_EMAIL").ok();
+
     Ok(User { name, email })
 }
 
+/// Get the user from the default global Git configuration.
+fn get_default_user() -> Result<User> {
+    log::info!(target: "cargo_spdx", "loading default git configuration");
+
+    let config = Config::open_default()?.snapshot()?;
+    get_config_user(&config)
+}
+
+/// Get the user from a snapshot of a Git configuration.
+fn get_config_user(config: &Config) -> Result<User> {
+    let name = config.get_str("user.name")?.to_owned();
+    let email = config.get_str("user.email").ok().map(ToOwned::to_owned);
+
+    Ok(User { name, email })
+}
+
+/// Check out the tree of the repository containing `dir` as of a git ref into `into`, leaving
+/// the working tree and index alone.
+///
+/// Returns the path in the checkout corresponding to `dir`.
+pub fn checkout_ref(dir: &Path, git_ref: &str, into: &Path) -> Result<PathBuf> {
+    log::info!(
+        target: "cargo_spdx",
+        "checking out '{}' into '{}'",
+        git_ref,
+        into.display()
+    );
+
+    let dir = fs::canonicalize(dir)?;
+    let repo = Repository::discover(&dir)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("the repository has no working tree"))?;
+    let workdir = fs::canonicalize(workdir)?;
+    let relative = dir.strip_prefix(&workdir)?;
+
+    let tree = repo.revparse_single(git_ref)?.peel_to_tree()?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.target_dir(into).update_index(false).force();
+    repo.checkout_tree(tree.as_object(), Some(&mut checkout))?;
+
+    Ok(into.join(relative))
+}
+
+/// The most commits walked when finding the authors of files.
+const MAX_AUTHOR_COMMITS: usize = 10_000;
+
+/// Get the distinct authors of the commits which touched each file in the repository
+/// containing `dir`, keyed by the file's absolute path.
+///
+/// The history is walked once for every file, and only the most recent
+/// `MAX_AUTHOR_COMMITS` commits are looked at, to bound the time taken on long histories.
+/// Merge commits are skipped, since their changes are credited to the commits merged.
+pub fn get_file_authors(dir: &Path) -> Result<HashMap<PathBuf, BTreeSet<String>>> {
+    log::info!(target: "cargo_spdx", "finding the authors of files from git history");
+
+    let repo = Repository::discover(dir)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("the repository has no working tree"))?;
+    let workdir = fs::canonicalize(workdir)?;
+
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    walk.set_sorting(Sort::TIME)?;
+
+    let mut authors = HashMap::<PathBuf, BTreeSet<String>>::new();
+
+    for (count, id) in walk.enumerate() {
+        if count == MAX_AUTHOR_COMMITS {
+            log::warn!(
+                target: "cargo_spdx",
+                "only the authors of the last {} commits are recorded as file contributors",
+                MAX_AUTHOR_COMMITS
+            );
+            break;
+        }
+
+        let commit = repo.find_commit(id?)?;
+
+        if commit.parent_count() > 1 {
+            continue;
+        }
+
+        let author = commit.author();
+        let author = match (author.name(), author.email()) {
+            (Some(name), Some(email)) => format!("{} <{}>", name, email),
+            (Some(name), None) => name.to_owned(),
+            _ => continue,
+        };
+
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+
+        for delta in diff.deltas() {
+            if let Some(path) = delta.new_file().path() {
+                authors
+                    .entry(workdir.join(path))
+                    .or_default()
+                    .insert(author.clone());
+            }
+        }
+    }
+
+    Ok(authors)
+}
+
+/// Get the origin remote of the repository containing the current directory.
+///
+/// The remote's URL is turned into an HTTPS URL, like `https://github.com/org/repo`, whether
+/// it's an HTTPS or SSH URL.
+pub fn get_origin() -> Result<Origin> {
+    log::info!(target: "cargo_spdx", "loading git origin remote");
+
+    let repo = Repository::discover(".")?;
+    let remote = repo.find_remote("origin")?;
+    let remote_url = remote
+        .url()
+        .ok_or_else(|| anyhow!("the origin remote's URL isn't valid UTF-8"))?;
+    let url = https_url(remote_url)
+        .ok_or_else(|| anyhow!("can't derive a URL from the origin remote '{}'", remote_url))?;
+    let commit = repo.head()?.peel_to_commit()?.id().to_string();
+
+    log::info!(target: "cargo_spdx", "detected git origin: {} at {}", url, commit);
+
+    Ok(Origin { url, commit })
+}
+
+/// Turn the URL of a remote into an HTTPS URL.
+///
+/// Both URLs with a scheme, like `ssh://git@host/org/repo.git`, and scp-like SSH addresses,
+/// like `git@host:org/repo.git`, are accepted.
+fn https_url(remote_url: &str) -> Option<String> {
+    let (host, path) = match Url::parse(remote_url) {
+        Ok(url) => (url.host_str()?.to_owned(), url.path().to_owned()),
+        Err(_) => {
+            let (user_host, path) = remote_url.split_once(':')?;
+            let host = user_host.rsplit('@').next()?;
+            (host.to_owned(), path.to_owned())
+        }
+    };
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+
+    Some(format!("https://{}/{}", host, path))
+}
+
+/// The origin remote of a repository.
+#[derive(Debug)]
+pub struct Origin {
+    /// The HTTPS URL of the remote.
+    pub url: String,
+    /// The commit checked out in the repository.
+    pub commit: String,
+}
+
 /// A user pulled from the Git config.
 #[derive(Debug)]
 pub struct User {
@@ -0,0 +1,327 @@
+//! Generate an SPDX SBOM for a Rust crate.
+//!
+//! This is the library behind `cargo spdx`, for tools which want to generate SBOMs
+//! without shelling out to the binary.
+
+#![deny(missing_debug_implementations)]
+#![deny(missing_copy_implementations)]
+#![deny(missing_docs)]
+
+use crate::cargo::CrateMetadata;
+use crate::document::SpdxValue;
+use anyhow::{Context as _, Result};
+use serde_json::Value;
+use std::io::Write;
+use std::ops::Not as _;
+use std::path::Path;
+
+pub use crate::cli::{Args, Command, GenerateOptions};
+pub use crate::diff::diff_refs;
+pub use crate::document::Document;
+pub use crate::error::ErrorKind;
+pub use crate::format::Format;
+pub use crate::output::{Compression, OutputManager};
+pub use crate::verify::verify_sbom;
+
+mod advisory;
+mod archive;
+mod binary;
+mod cargo;
+mod cli;
+mod config;
+mod diff;
+mod document;
+mod error;
+mod format;
+mod git;
+mod output;
+mod registry;
+mod schema;
+mod script;
+#[cfg(test)]
+mod test_support;
+mod verify;
+
+/// Generate an SBOM for the crate in the current directory.
+///
+/// If given, `post_process` is called with the built document, so it can be adjusted before
+/// it's checked and written out. Fails if more packages than allowed have an unknown license,
+/// if any package has a license the license policy doesn't permit, or if `--validate` was
+/// given and the document doesn't match the SPDX 2.3 JSON schema. Errors are categorized
+/// with an [`ErrorKind`] where possible.
+pub fn generate_sbom(
+    options: &GenerateOptions,
+    post_process: Option<&mut dyn FnMut(&mut Document)>,
+) -> Result<Document> {
+    // Load the crate metadata, and name the document after the file it'll be written to.
+    let metadata = CrateMetadata::load(options).context(ErrorKind::Metadata)?;
+    let output_file_name = OutputManager::new(options, metadata.name()?).output_file_name();
+
+    let mut doc = document::build(options, &output_file_name, &metadata)?;
+
+    if let Some(post_process) = post_process {
+        post_process(&mut doc);
+    }
+
+    // A relationship to a missing element would make the document invalid.
+    doc.check_relationships().context(ErrorKind::Validation)?;
+
+    if options.allowed_licenses().is_empty().not() || options.denied_licenses().is_empty().not() {
+        doc.check_license_policy(options.allowed_licenses(), options.denied_licenses())
+            .context(ErrorKind::Validation)?;
+    }
+
+    if let Some(max) = options.max_unknown_licenses() {
+        doc.check_unknown_licenses(max)
+            .context(ErrorKind::Validation)?;
+    }
+
+    if options.validate() {
+        schema::validate(&format::json::to_value(&doc)).context(ErrorKind::Validation)?;
+    }
+
+    Ok(doc)
+}
+
+/// Generate an SBOM for a prebuilt binary from the dependency information `cargo auditable`
+/// embedded in it.
+///
+/// The document is named after the binary. Errors are categorized with an [`ErrorKind`]
+/// where possible.
+pub fn generate_binary_sbom(options: &GenerateOptions, path: &Path) -> Result<Document> {
+    let name = path
+        .file_stem()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let output_file_name = OutputManager::new(options, &name).output_file_name();
+
+    let doc = document::build_from_binary(options, &output_file_name, path)
+        .context(ErrorKind::Metadata)?;
+
+    // A relationship to a missing element would make the document invalid.
+    doc.check_relationships().context(ErrorKind::Validation)?;
+
+    if options.validate() {
+        schema::validate(&format::json::to_value(&doc)).context(ErrorKind::Validation)?;
+    }
+
+    Ok(doc)
+}
+
+/// Pass an SBOM through the post-processing script, if one was given, returning the JSON
+/// object to write out in its place.
+///
+/// The script is run once, however many formats the SBOM is written out in, and should be
+/// run before anything is written, so a failing script leaves nothing written. It isn't run
+/// in dry runs. If `--validate` was given, the object it prints must match the SPDX 2.3 JSON
+/// schema too.
+pub fn post_process_sbom(options: &GenerateOptions, doc: &Document) -> Result<Option<Value>> {
+    let script = match options.post_process_script() {
+        Some(script) => script,
+        None => return Ok(None),
+    };
+
+    if options.dry_run() {
+        log::info!(
+            target: "cargo_spdx",
+            "skipping post-processing script '{}' in a dry run",
+            script.display()
+        );
+        return Ok(None);
+    }
+
+    let object =
+        script::post_process(script, &format::json::to_value(doc)).context(ErrorKind::Io)?;
+
+    if options.validate() {
+        schema::validate(&object).context(ErrorKind::Validation)?;
+    }
+
+    Ok(Some(object))
+}
+
+/// Write an SBOM out through an output manager, returning the number of bytes written.
+///
+/// JSON and YAML output is written from `processed` instead, if given, which is the SBOM's
+/// JSON object as returned by [`post_process_sbom`].
+pub fn write_sbom(doc: &Document, processed: Option<&Value>, out: &OutputManager) -> Result<usize> {
+    out.write_document(doc, processed).context(ErrorKind::Io)
+}
+
+/// Write an SBOM out through an output manager as an archive of smaller documents, one for
+/// each package, returning the number of bytes written.
+pub fn write_sbom_archive(doc: &Document, out: &OutputManager) -> Result<usize> {
+    let docs = doc.split_by_package(out.format().extension());
+    out.write_archive(&docs).context(ErrorKind::Io)
+}
+
+/// Write a summary of an SBOM written out through an output manager to `w`, like stderr.
+pub fn print_summary<W: Write>(
+    mut w: W,
+    doc: &Document,
+    out: &OutputManager,
+    bytes: usize,
+) -> Result<()> {
+    let unknown_licenses = doc
+        .package_information
+        .iter()
+        .filter(|package| package.declared_license == SpdxValue::NoAssertion)
+        .count();
+
+    writeln!(w, "wrote {}", out.destination())?;
+    writeln!(w, "  format: {}", out.format())?;
+    writeln!(w, "  packages: {}", doc.package_information.len())?;
+    writeln!(w, "  relationships: {}", doc.relationships.len())?;
+    writeln!(w, "  unknown licenses: {}", unknown_licenses)?;
+    writeln!(w, "  bytes: {}", bytes)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MetadataFixture, TempDir};
+    use std::ops::Not as _;
+
+    #[test]
+    fn summaries_count_packages_and_unknown_licenses() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package_with("fixture", "0.1.0", |json| {
+            json["license"] = serde_json::json!("MIT");
+        });
+        let unlicensed = fixture.package("unlicensed", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &unlicensed);
+
+        let path = dir.path().join("fixture.spdx.json");
+        let (options, metadata) = fixture.load(&["-o", path.to_str().unwrap()]);
+        let doc = document::build(&options, "fixture.spdx.json", &metadata).unwrap();
+        let out = OutputManager::for_document(&options, &doc);
+
+        let mut summary = Vec::new();
+        print_summary(&mut summary, &doc, &out, 1234).unwrap();
+
+        assert_eq!(
+            String::from_utf8(summary).unwrap(),
+            format!(
+                "wrote {}\n  \
+                 format: JSON\n  \
+                 packages: 2\n  \
+                 relationships: {}\n  \
+                 unknown licenses: 1\n  \
+                 bytes: 1234\n",
+                path.display(),
+                doc.relationships.len()
+            )
+        );
+    }
+
+    /// Write an executable post-processing script into `dir`, returning its path.
+    #[cfg(unix)]
+    fn write_script(dir: &TempDir, body: &str) -> String {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let path = dir.write("post-process.sh", format!("#!/bin/sh\n{}\n", body));
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Load a fixture crate with the given options.
+    fn fixture(dir: &TempDir, args: &[&str]) -> (GenerateOptions, Document) {
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        fixture.member(&root);
+
+        let (options, metadata) = fixture.load(args);
+        let doc = document::build(&options, "fixture.spdx.json", &metadata).unwrap();
+        (options, doc)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scripts_run_once_for_every_format() {
+        let dir = TempDir::new();
+        let runs = dir.path().join("runs");
+        let script = write_script(
+            &dir,
+            &format!(
+                "echo run >> '{}'\ncat > /dev/null\necho '{{\"name\": \"processed\"}}'",
+                runs.display()
+            ),
+        );
+        let path = dir.path().join("fixture.spdx.json");
+        let (options, doc) = fixture(
+            &dir,
+            &[
+                "-o",
+                path.to_str().unwrap(),
+                "--post-process-script",
+                &script,
+            ],
+        );
+
+        let processed = post_process_sbom(&options, &doc).unwrap();
+        let json = OutputManager::for_document(&options, &doc);
+        let yaml = json.for_format(Format::Yaml).unwrap();
+        write_sbom(&doc, processed.as_ref(), &json).unwrap();
+        write_sbom(&doc, processed.as_ref(), &yaml).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&runs).unwrap(), "run\n");
+        let written: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written, serde_json::json!({ "name": "processed" }));
+        assert!(
+            std::fs::read_to_string(dir.path().join("fixture.spdx.yaml"))
+                .unwrap()
+                .contains("name: processed")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_scripts_leave_nothing_written() {
+        let dir = TempDir::new();
+        let script = write_script(&dir, "exit 1");
+        let path = dir.path().join("fixture.spdx.json");
+        let (options, doc) = fixture(
+            &dir,
+            &[
+                "-o",
+                path.to_str().unwrap(),
+                "--post-process-script",
+                &script,
+            ],
+        );
+
+        let error = post_process_sbom(&options, &doc).unwrap_err();
+
+        assert_eq!(ErrorKind::of(&error), Some(ErrorKind::Io));
+        assert!(path.exists().not());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scripts_are_skipped_in_dry_runs() {
+        let dir = TempDir::new();
+        let runs = dir.path().join("runs");
+        let script = write_script(&dir, &format!("echo run >> '{}'\ncat", runs.display()));
+        let (options, doc) = fixture(&dir, &["--dry-run", "--post-process-script", &script]);
+
+        assert_eq!(post_process_sbom(&options, &doc).unwrap(), None);
+        assert!(runs.exists().not());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn processed_documents_are_validated() {
+        let dir = TempDir::new();
+        let script = write_script(&dir, "cat > /dev/null\necho '{}'");
+        let (options, doc) = fixture(&dir, &["--validate", "--post-process-script", &script]);
+
+        let error = post_process_sbom(&options, &doc).unwrap_err();
+
+        assert_eq!(ErrorKind::of(&error), Some(ErrorKind::Validation));
+        assert!(format!("{:#}", error).contains("spdxVersion: missing"));
+    }
+}
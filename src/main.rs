@@ -4,45 +4,117 @@
 #![deny(missing_copy_implementations)]
 #![deny(missing_docs)]
 
-use crate::cargo::CrateMetadata;
-use crate::cli::Args;
-use crate::format::Format;
-use crate::output::OutputManager;
-use anyhow::Result;
-
-mod cargo;
-mod cli;
-mod document;
-mod format;
-mod git;
-mod output;
-
-/// Program entrypoint, only inits the system, calls `run` and reports errors.
-fn main() {
-    init();
+use anyhow::{Context as _, Result};
+use cargo_spdx::{
+    diff_refs, generate_binary_sbom, generate_sbom, post_process_sbom, print_summary, verify_sbom,
+    write_sbom, write_sbom_archive, Args, Command, ErrorKind, OutputManager,
+};
+use std::io;
+use std::ops::Not as _;
+use std::process;
 
+/// Program entrypoint, only calls `run` and reports errors.
+///
+/// The exit code says which category of failure happened, as documented on `ErrorKind`.
+fn main() {
     if let Err(e) = run() {
-        eprintln!("error: {}", e);
+        // Parsing errors, along with help and version output, are reported by clap, which
+        // exits with the same code as other usage errors.
+        if let Some(e) = e.downcast_ref::<clap::Error>() {
+            e.exit();
+        }
+
+        eprintln!("error: {:#}", e);
+        process::exit(ErrorKind::of(&e).map_or(1, |kind| kind.exit_code()));
     }
 }
 
 /// Initialize the context needed to run.
-fn init() {
-    // Start the environment logger.
-    env_logger::init();
+fn init(args: &Args) {
+    // Start the environment logger, at the level picked on the CLI if there is one.
+    let mut builder = match args.log_level() {
+        Some(level) => {
+            let mut builder = env_logger::Builder::new();
+            builder.filter_level(level);
+            builder
+        }
+        None => env_logger::Builder::from_default_env(),
+    };
+
+    builder.init();
 }
 
 /// Gathers CLI args, constructs an SPDX `Document`, and outputs that document.
+///
+/// If asked to verify an existing document instead, it's compared with the constructed one.
+/// If asked to diff two git refs instead, the changes to the locked packages are printed.
+/// If given a prebuilt binary, the document is constructed from the dependency information
+/// embedded in it instead.
 fn run() -> Result<()> {
-    // Load the CLI args and crate metadata, and then figure out where the SPDX file
-    // will be written, setting up a manager to ensure we only write when conditions are met.
-    let args = Args::read()?;
-    let metadata = CrateMetadata::load()?;
-    let output_manager = OutputManager::new(&args, metadata.root()?);
+    // Load the CLI args and build the document.
+    let args = Args::read().context(ErrorKind::Usage)?;
+    init(&args);
+
+    let doc = match args.command() {
+        Some(Command::Verify { path }) => return verify_sbom(&args, path),
+        Some(Command::Diff { from, to }) => {
+            for difference in diff_refs(&args, from, to.as_deref())? {
+                println!("{}", difference);
+            }
+
+            return Ok(());
+        }
+        Some(Command::SbomForBinary { path }) => generate_binary_sbom(&args, path)?,
+        None => generate_sbom(&args, None)?,
+    };
+
+    // Figure out where the SPDX file will be written, setting up a manager to ensure we
+    // only write when conditions are met, and then write the document out.
+    // What was written is summarized, except in dry runs, which already print what would
+    // have been.
+    let summarize = args.quiet().not() && args.dry_run().not();
+
+    // An archive of per-package documents is written instead of the whole document.
+    if let Some(output_manager) = OutputManager::for_archive(&args) {
+        let bytes = write_sbom_archive(&doc, &output_manager)?;
+
+        if summarize {
+            print_summary(io::stderr(), &doc, &output_manager, bytes).context(ErrorKind::Io)?;
+        }
+
+        return Ok(());
+    }
+
+    // The script runs before any file is opened, so if it fails nothing is written.
+    let processed = post_process_sbom(&args, &doc)?;
+
+    // The same document is written out again in any other formats requested.
+    let output_manager = OutputManager::for_document(&args, &doc);
+    let mut output_managers = vec![];
+
+    for format in args.also_formats() {
+        output_managers.push(
+            output_manager
+                .for_format(*format)
+                .context(ErrorKind::Usage)?,
+        );
+    }
 
-    // Build the document.
-    let doc = document::build(&args, &output_manager.output_file_name())?;
+    output_managers.insert(0, output_manager);
+
+    // Every file is checked before any is written, so one which can't be doesn't leave the
+    // others written without it.
+    for output_manager in &output_managers {
+        output_manager.check_target().context(ErrorKind::Io)?;
+    }
+
+    for output_manager in &output_managers {
+        let bytes = write_sbom(&doc, processed.as_ref(), output_manager)?;
+
+        if summarize {
+            print_summary(io::stderr(), &doc, output_manager, bytes).context(ErrorKind::Io)?;
+        }
+    }
 
-    // Write the document to the output file.
-    output_manager.write_document(doc)
+    Ok(())
 }
@@ -1,42 +1,205 @@
 //! Handle outputting the document to the user.
 
+use crate::archive::TarWriter;
 use crate::document::Document;
-use crate::{format, Args, Format};
+use crate::format::JsonStyle;
+use crate::{format, Format, GenerateOptions};
 use anyhow::{anyhow, Result};
-use cargo_metadata::Package;
-use std::ffi::OsStr;
-use std::fs::File;
-use std::io::{BufWriter, Write};
+use clap::ArgEnum;
+use flate2::write::GzEncoder;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
 use std::ops::Not as _;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Handles writing to the correct path.
 #[derive(Debug)]
 pub struct OutputManager {
     /// The path to be written to.
     to: PathBuf,
+    /// Whether to write to stdout instead of the path.
+    stdout: bool,
     /// The format to write the output in.
     format: Format,
+    /// How JSON output is laid out.
+    json_style: JsonStyle,
+    /// Whether YAML output begins with a document start marker.
+    yaml_document_start: bool,
     /// Whether output is being forced.
     force: bool,
+    /// Whether the parent directory of the path should be created if it's missing.
+    create_dir: bool,
+    /// How the output is compressed, if at all.
+    compression: Option<Compression>,
+    /// Whether to only summarize the output, rather than writing it.
+    dry_run: bool,
+}
+
+/// A writer which counts the bytes written through it, discarding them if there's nowhere
+/// to write them to.
+#[derive(Debug, Default)]
+struct ByteCounter<W = io::Sink> {
+    /// Where the bytes are written.
+    inner: W,
+    /// How many bytes have been written.
+    count: usize,
+}
+
+impl<W: Write> Write for ByteCounter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A compression scheme for the output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ArgEnum)]
+pub enum Compression {
+    /// Gzip compression.
+    Gzip,
+}
+
+impl Compression {
+    /// Get the file extension for the compression scheme.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => ".gz",
+        }
+    }
 }
 
 impl OutputManager {
-    /// Get a new output manager based on CLI args and package info.
-    pub fn new(args: &Args, pkg: &Package) -> Self {
+    /// Get a new output manager based on CLI args and the name of the document.
+    pub fn new(args: &GenerateOptions, name: &str) -> Self {
+        // The default path is based on the name of the document and the format selected
+        // by the user.
+        let name = match args.timestamp_suffix() {
+            Some(timestamp) => format!("{}-{}", name, timestamp),
+            None => name.to_owned(),
+        };
+
+        Self::with_default_path(args, format!("{}{}", name, args.format_extension()).into())
+    }
+
+    /// Get a new output manager for writing out a document built with the same options.
+    pub fn for_document(args: &GenerateOptions, doc: &Document) -> Self {
+        // The document is named after the file it's written to, so that's the default path,
+        // already carrying any timestamp.
+        Self::with_default_path(args, doc.document_name.to_string().into())
+    }
+
+    /// Get a new output manager for writing an archive of per-package documents, if the user
+    /// asked for one.
+    pub fn for_archive(args: &GenerateOptions) -> Option<Self> {
+        // The archive path can't be given with other output paths, so it's always used.
+        args.archive()
+            .map(|path| Self::with_default_path(args, path.to_owned()))
+    }
+
+    /// Get a new output manager, writing to `default_path` if the user didn't pick a path.
+    fn with_default_path(args: &GenerateOptions, default_path: PathBuf) -> Self {
         log::info!(target: "cargo_spdx", "determining output path");
 
-        // It's either the specified path, or a default path based on the name of the root package
-        // and the format selected by the user.
-        let to = args
+        // An output path of '-' means the document is written to stdout.
+        let stdout = args.output() == Some(Path::new("-"));
+
+        // It's either the specified path, or the default path, placed in the output directory
+        // if there is one. When writing to stdout the default path is still used to name
+        // the document.
+        let mut to = args
             .output()
-            .map(ToOwned::to_owned)
-            .unwrap_or_else(|| format!("{}{}", pkg.name, args.format_extension()).into());
+            .filter(|_| stdout.not())
+            .map(|path| match args.timestamp_suffix() {
+                Some(timestamp) => add_timestamp(
+                    path,
+                    args.format_extension(),
+                    args.compression(),
+                    &timestamp,
+                ),
+                None => path.to_owned(),
+            })
+            .unwrap_or_else(|| match args.output_directory() {
+                Some(dir) => dir.join(default_path),
+                None => default_path,
+            });
+
+        // Compressed output gets the extension of the compression scheme too.
+        let compression = args.compression();
+
+        if let Some(compression) = compression {
+            let extension = compression.extension();
+
+            if to.to_string_lossy().ends_with(extension).not() {
+                let mut path = OsString::from(to);
+                path.push(extension);
+                to = PathBuf::from(path);
+            }
+        }
 
         let format = args.format();
+        let json_style = args.json_style();
         let force = args.force();
+        // Forced output goes wherever it's pointed, creating directories as needed.
+        let create_dir = args.output_directory().is_some() || force;
 
-        OutputManager { to, format, force }
+        OutputManager {
+            to,
+            stdout,
+            format,
+            json_style,
+            yaml_document_start: args.yaml_document_start(),
+            force,
+            create_dir,
+            compression,
+            dry_run: args.dry_run(),
+        }
+    }
+
+    /// Get an output manager for writing the same document out in another format.
+    ///
+    /// The path is the same, with the extension of the other format. Writing to stdout
+    /// can only be done in one format.
+    pub fn for_format(&self, format: Format) -> Result<Self> {
+        if self.stdout {
+            return Err(anyhow!(
+                "can't write {} output as well when writing to stdout",
+                format
+            ));
+        }
+
+        // Swap the extension of this format for the other, keeping any compression extension.
+        let mut base = self.to.to_string_lossy().into_owned();
+
+        if let Some(compression) = self.compression {
+            base = base
+                .strip_suffix(compression.extension())
+                .unwrap_or(&base)
+                .to_owned();
+        }
+
+        let base = base.strip_suffix(self.format.extension()).unwrap_or(&base);
+        let compression_extension = self.compression.map_or("", |c| c.extension());
+        let to = format!("{}{}{}", base, format.extension(), compression_extension);
+
+        Ok(OutputManager {
+            to: PathBuf::from(to),
+            stdout: false,
+            format,
+            json_style: self.json_style,
+            yaml_document_start: self.yaml_document_start,
+            force: self.force,
+            create_dir: self.create_dir,
+            compression: self.compression,
+            dry_run: self.dry_run,
+        })
     }
 
     /// Get the name of the output file.
@@ -52,10 +215,107 @@ impl OutputManager {
             .to_string()
     }
 
-    /// Write the document to the output file in the specified format.
+    /// Get the format the document is written in.
+    #[inline]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Get where the document is written, for showing to the user.
+    #[inline]
+    pub fn destination(&self) -> String {
+        if self.stdout {
+            String::from("stdout")
+        } else {
+            self.to.display().to_string()
+        }
+    }
+
+    /// Write the document to the output file in the specified format, returning the number
+    /// of bytes written.
+    ///
+    /// JSON and YAML output is written from `processed` instead, if given, which is the
+    /// document's JSON object after post-processing. In a dry run, nothing is written, and a
+    /// summary of what would have been is printed instead.
     #[inline]
-    pub fn write_document(&self, doc: Document) -> Result<()> {
-        // Check the output file has a file name and isn't a directory.
+    pub fn write_document(&self, doc: &Document, processed: Option<&Value>) -> Result<usize> {
+        self.check_target()?;
+
+        if self.dry_run {
+            return self.summarize_document(doc, processed);
+        }
+
+        // Get the writer to the output file, and write the document out, counting the bytes
+        // which reach it after any compression.
+        let counter = ByteCounter {
+            inner: self.get_uncompressed_writer()?,
+            count: 0,
+        };
+        let mut counter =
+            self.compress(counter, |writer| self.write_format(writer, doc, processed))?;
+        counter.flush()?;
+
+        Ok(counter.count)
+    }
+
+    /// Write a tar archive of documents to the output file, each in the specified format and
+    /// named after the document, returning the number of bytes written.
+    ///
+    /// Compression applies to the whole archive. In a dry run, nothing is written, and a
+    /// summary of what would have been is printed instead.
+    pub fn write_archive(&self, docs: &[Document]) -> Result<usize> {
+        if self.to.file_name().is_none() {
+            return Err(anyhow!("missing archive file name"));
+        }
+
+        if self.to.is_dir() {
+            return Err(anyhow!("archive can't be a directory"));
+        }
+
+        // In a dry run the archive is still written out, just to nowhere, to measure it.
+        let inner: Box<dyn Write> = if self.dry_run {
+            self.check_overwrite()?;
+            Box::new(io::sink())
+        } else {
+            self.get_uncompressed_writer()?
+        };
+
+        let counter = ByteCounter { inner, count: 0 };
+        let mut counter = self.compress(counter, |writer| {
+            let mut archive = TarWriter::new(writer);
+
+            for doc in docs {
+                let mut contents = Vec::new();
+                self.write_format(&mut contents, doc, None)?;
+
+                let mtime = u64::try_from(doc.created.0.unix_timestamp()).unwrap_or(0);
+                archive.append(&doc.document_name.0, &contents, mtime)?;
+            }
+
+            archive.finish()?;
+            Ok(())
+        })?;
+        counter.flush()?;
+
+        if self.dry_run {
+            println!("format: {}", self.format);
+            println!("output: {}", self.destination());
+            println!("documents: {}", docs.len());
+            println!("bytes: {}", counter.count);
+        }
+
+        Ok(counter.count)
+    }
+
+    /// Check the document can be written to the output file, without writing anything.
+    ///
+    /// Returns an error if the output file has no file name, is a directory, or already
+    /// exists and the user hasn't set output to be forced.
+    pub fn check_target(&self) -> Result<()> {
+        if self.stdout {
+            return Ok(());
+        }
+
         if self.to.file_name().is_none() {
             return Err(anyhow!("missing output file name"));
         }
@@ -64,21 +324,76 @@ impl OutputManager {
             return Err(anyhow!("output can't be a directory"));
         }
 
-        // Get the writer to the output file.
-        let mut writer = self.get_writer()?;
+        self.check_overwrite()
+    }
+
+    /// Print a summary of the output without writing it, returning the number of bytes which
+    /// would have been written.
+    fn summarize_document(&self, doc: &Document, processed: Option<&Value>) -> Result<usize> {
+        // The document is still written out, just to nowhere, to measure it.
+        let counter: ByteCounter = ByteCounter::default();
+        let counter = self.compress(counter, |writer| self.write_format(writer, doc, processed))?;
+
+        println!("format: {}", self.format);
+        println!("output: {}", self.destination());
+        println!("packages: {}", doc.package_information.len());
+        println!("bytes: {}", counter.count);
+        Ok(counter.count)
+    }
 
-        // Write the document out in the requested format.
+    /// Write the document out in the requested format, writing JSON and YAML from the
+    /// post-processed object if there is one.
+    fn write_format(
+        &self,
+        writer: &mut dyn Write,
+        doc: &Document,
+        processed: Option<&Value>,
+    ) -> Result<()> {
         match self.format {
-            Format::KeyValue => Ok(format::key_value::write(&mut writer, &doc)?),
+            Format::KeyValue => Ok(format::key_value::write(writer, doc)?),
+            Format::Json => Ok(format::json::write_value(
+                writer,
+                &json_object(doc, processed),
+                self.json_style,
+            )?),
+            Format::Yaml => Ok(format::yaml::write_value(
+                writer,
+                &json_object(doc, processed),
+                self.yaml_document_start,
+            )?),
+            Format::Spdx3 => Ok(format::spdx3::write(writer, doc, self.json_style)?),
+            Format::Ndjson => Ok(format::ndjson::write(writer, doc)?),
             _ => Err(anyhow!("{} format not yet implemented", self.format)),
         }
     }
 
-    /// Get a writer to the output file.
+    /// Call `write` with a writer compressing what's written into `writer`, if compression
+    /// was requested, and finish the compression once it's done.
+    ///
+    /// Returns the writer, so it can be flushed.
+    fn compress<W: Write>(
+        &self,
+        mut writer: W,
+        write: impl FnOnce(&mut dyn Write) -> Result<()>,
+    ) -> Result<W> {
+        match self.compression {
+            Some(Compression::Gzip) => {
+                let mut encoder = GzEncoder::new(writer, flate2::Compression::default());
+                write(&mut encoder)?;
+                Ok(encoder.finish()?)
+            }
+            None => {
+                write(&mut writer)?;
+                Ok(writer)
+            }
+        }
+    }
+
+    /// Check the output file may be written.
     ///
     /// Returns an error if the output file already exists and the user hasn't set output
     /// to be forced.
-    fn get_writer(&self) -> Result<Box<dyn Write>> {
+    fn check_overwrite(&self) -> Result<()> {
         // A little truth table making clear this conditional is the right one.
         //
         // ---------
@@ -87,10 +402,188 @@ impl OutputManager {
         // | F | T | - not forcing and exists - error
         // | F | F | - not forcing and doesn't exist - no error
         // ---------
-        if self.force.not() && self.to.exists() {
+        if self.stdout.not() && self.force.not() && self.to.exists() {
             return Err(anyhow!("output file already exists"));
         }
 
+        Ok(())
+    }
+
+    /// Get a writer to the output file, or to stdout, without any compression applied.
+    ///
+    /// Returns an error if the output file already exists and the user hasn't set output
+    /// to be forced.
+    fn get_uncompressed_writer(&self) -> Result<Box<dyn Write>> {
+        if self.stdout {
+            return Ok(Box::new(BufWriter::new(io::stdout())));
+        }
+
+        self.check_overwrite()?;
+
+        if self.create_dir {
+            if let Some(dir) = self.to.parent() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+
         Ok(Box::new(BufWriter::new(File::create(&self.to)?)))
     }
 }
+
+/// Insert a timestamp into the file name of a path, before the extension of the format if
+/// it has it, or otherwise before its last extension. Any compression extension stays last.
+fn add_timestamp(
+    path: &Path,
+    format_extension: &str,
+    compression: Option<Compression>,
+    timestamp: &str,
+) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    let compression_extension = compression.map_or("", |c| c.extension());
+    let (name, compression_extension) = match name.strip_suffix(compression_extension) {
+        Some(name) => (name, compression_extension),
+        None => (name.as_ref(), ""),
+    };
+
+    let (stem, extension) = match name.strip_suffix(format_extension) {
+        Some(stem) => (stem, format_extension),
+        None => match name.rfind('.').filter(|index| *index > 0) {
+            Some(index) => name.split_at(index),
+            None => (name, ""),
+        },
+    };
+
+    path.with_file_name(format!(
+        "{}-{}{}{}",
+        stem, timestamp, extension, compression_extension
+    ))
+}
+
+/// Get the object written out as JSON or YAML: the post-processed object if there is one, or
+/// the document's own.
+fn json_object<'a>(doc: &Document, processed: Option<&'a Value>) -> Cow<'a, Value> {
+    match processed {
+        Some(object) => Cow::Borrowed(object),
+        None => Cow::Owned(format::json::to_value(doc)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document;
+    use crate::test_support::{MetadataFixture, TempDir};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use std::ops::Not as _;
+
+    /// Build the document of a fixture crate, with the options it was built with.
+    fn fixture(dir: &TempDir, args: &[&str]) -> (GenerateOptions, Document) {
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        fixture.member(&root);
+
+        let (options, metadata) = fixture.load(args);
+        let doc = document::build(&options, "fixture.spdx.json", &metadata).unwrap();
+        (options, doc)
+    }
+
+    /// Read and decompress a gzipped file.
+    fn gunzip(path: &Path) -> Vec<u8> {
+        let mut contents = Vec::new();
+        GzDecoder::new(File::open(path).unwrap())
+            .read_to_end(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn gzip_extension_implies_json_and_compression() {
+        let dir = TempDir::new();
+        let path = dir.path().join("out.json.gz");
+        let (options, _) = fixture(&dir, &["-o", path.to_str().unwrap()]);
+
+        assert_eq!(options.format(), Format::Json);
+        assert_eq!(options.compression(), Some(Compression::Gzip));
+    }
+
+    #[test]
+    fn compressed_documents_are_complete() {
+        let dir = TempDir::new();
+        let path = dir.path().join("out.json.gz");
+        let (options, doc) = fixture(&dir, &["-o", path.to_str().unwrap()]);
+
+        let bytes = OutputManager::for_document(&options, &doc)
+            .write_document(&doc, None)
+            .unwrap();
+
+        assert_eq!(bytes as u64, fs::metadata(&path).unwrap().len());
+
+        let object: Value = serde_json::from_slice(&gunzip(&path)).unwrap();
+        assert_eq!(object["packages"][0]["name"], "fixture");
+    }
+
+    #[test]
+    fn compressed_archives_are_complete() {
+        let dir = TempDir::new();
+        let path = dir.path().join("out.tar");
+        let (options, doc) = fixture(
+            &dir,
+            &["--archive", path.to_str().unwrap(), "--compress", "gzip"],
+        );
+
+        let out = OutputManager::for_archive(&options).unwrap();
+        let docs = doc.split_by_package(out.format().extension());
+        out.write_archive(&docs).unwrap();
+
+        let archive = gunzip(&dir.path().join("out.tar.gz"));
+
+        // The archive is only complete once the two empty end blocks are written.
+        assert_eq!(archive.len() % 512, 0);
+        assert!(archive[archive.len() - 2 * 512..]
+            .iter()
+            .all(|byte| *byte == 0));
+        assert!(archive.starts_with(docs[0].document_name.0.as_bytes()));
+    }
+
+    #[test]
+    fn forced_output_creates_missing_directories() {
+        let dir = TempDir::new();
+        let path = dir.path().join("dist/sbom/fixture.spdx");
+        let (options, doc) = fixture(&dir, &["-o", path.to_str().unwrap(), "--force"]);
+
+        OutputManager::for_document(&options, &doc)
+            .write_document(&doc, None)
+            .unwrap();
+
+        assert!(path.is_file());
+    }
+
+    #[test]
+    fn unforced_output_needs_existing_directories() {
+        let dir = TempDir::new();
+        let path = dir.path().join("dist/sbom/fixture.spdx");
+        let (options, doc) = fixture(&dir, &["-o", path.to_str().unwrap()]);
+
+        let result = OutputManager::for_document(&options, &doc).write_document(&doc, None);
+
+        assert!(result.is_err());
+        assert!(dir.path().join("dist").exists().not());
+    }
+
+    #[test]
+    fn existing_targets_are_found_before_writing() {
+        let dir = TempDir::new();
+        let path = dir.path().join("fixture.spdx");
+        let (options, doc) = fixture(&dir, &["-o", path.to_str().unwrap()]);
+        dir.write("fixture.spdx.json", "{}");
+
+        let primary = OutputManager::for_document(&options, &doc);
+        let json = primary.for_format(Format::Json).unwrap();
+
+        assert!(primary.check_target().is_ok());
+        assert!(json.check_target().is_err());
+        assert!(path.exists().not());
+    }
+}
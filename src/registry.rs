@@ -0,0 +1,83 @@
+//! Functions for looking up packages in the crates.io index.
+
+use crate::GenerateOptions;
+use cargo_metadata::Package;
+use std::ops::Not as _;
+
+/// The crates.io index.
+pub struct RegistryIndex(
+    /// The index.
+    crates_index::Index,
+);
+
+impl RegistryIndex {
+    /// Open Cargo's copy of the crates.io index, updating it unless running offline.
+    ///
+    /// Failing to open the index isn't fatal, so failures are logged and `None` is returned
+    /// instead. Failing to update it just means the cached copy is used.
+    pub fn fetch(args: &GenerateOptions) -> Option<Self> {
+        if args.offline() || args.frozen() {
+            log::warn!(
+                target: "cargo_spdx",
+                "running offline, so the crates.io index won't be checked"
+            );
+            return None;
+        }
+
+        log::info!(target: "cargo_spdx", "opening the crates.io index");
+
+        let mut index = match crates_index::Index::new_cargo_default() {
+            Ok(index) => index,
+            Err(e) => {
+                log::warn!(
+                    target: "cargo_spdx",
+                    "failed to open the crates.io index, so it won't be checked: {}",
+                    e
+                );
+                return None;
+            }
+        };
+
+        if let Err(e) = index.update() {
+            log::warn!(
+                target: "cargo_spdx",
+                "failed to update the crates.io index, using the cached copy: {}",
+                e
+            );
+        }
+
+        Some(RegistryIndex(index))
+    }
+
+    /// Check whether the version of a package from crates.io has been yanked.
+    ///
+    /// Packages from anywhere else are never considered yanked.
+    pub fn is_yanked(&self, package: &Package) -> bool {
+        if package
+            .source
+            .as_ref()
+            .map_or(false, |source| source.is_crates_io())
+            .not()
+        {
+            return false;
+        }
+
+        let version = package.version.to_string();
+
+        self.0.crate_(&package.name).map_or(false, |krate| {
+            krate
+                .versions()
+                .iter()
+                .any(|v| v.version() == version && v.is_yanked())
+        })
+    }
+
+    /// Check whether crates.io has the same version of a package, wherever it came from.
+    pub fn is_published(&self, package: &Package) -> bool {
+        let version = package.version.to_string();
+
+        self.0.crate_(&package.name).map_or(false, |krate| {
+            krate.versions().iter().any(|v| v.version() == version)
+        })
+    }
+}
@@ -0,0 +1,151 @@
+//! Checks documents against the SPDX 2.3 JSON schema.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::ops::Not as _;
+
+/// The SPDX 2.3 JSON schema.
+const SCHEMA: &str = include_str!("../resources/spdx-schema-2.3.json");
+
+/// Check the JSON object of a document against the SPDX 2.3 JSON schema.
+///
+/// Returns an error listing every field which violates the schema.
+pub fn validate(object: &Value) -> Result<()> {
+    // PANIC SAFETY: The schema is bundled with the binary, and is valid JSON.
+    let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+
+    let mut violations = vec![];
+    check(&schema, object, "", &mut violations);
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "the document doesn't match the SPDX 2.3 JSON schema, {} fields are invalid:\n  {}",
+        violations.len(),
+        violations.join("\n  ")
+    ))
+}
+
+/// Check a value against a schema, recording each violation along with the path to the field.
+///
+/// Only the keywords the bundled schema uses are supported: `type`, `enum`, `required`,
+/// `properties`, `items`, and `minItems`.
+fn check(schema: &Value, value: &Value, path: &str, violations: &mut Vec<String>) {
+    let field = if path.is_empty() { "document" } else { path };
+
+    if let Some(expected) = schema["type"].as_str() {
+        if has_type(value, expected).not() {
+            violations.push(format!("{}: expected {}, found {}", field, expected, value));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema["enum"].as_array() {
+        if allowed.contains(value).not() {
+            violations.push(format!("{}: {} isn't an allowed value", field, value));
+        }
+    }
+
+    if let Value::Object(object) = value {
+        for required in schema["required"].as_array().into_iter().flatten() {
+            if let Some(key) = required.as_str() {
+                if object.contains_key(key).not() {
+                    violations.push(format!("{}: missing", join(path, key)));
+                }
+            }
+        }
+
+        if let Some(properties) = schema["properties"].as_object() {
+            for (key, value) in object {
+                if let Some(schema) = properties.get(key) {
+                    check(schema, value, &join(path, key), violations);
+                }
+            }
+        }
+    }
+
+    if let Value::Array(items) = value {
+        if let Some(min) = schema["minItems"].as_u64() {
+            if (items.len() as u64) < min {
+                violations.push(format!("{}: expected at least {} items", field, min));
+            }
+        }
+
+        if schema["items"].is_object() {
+            for (index, item) in items.iter().enumerate() {
+                let path = format!("{}[{}]", field, index);
+                check(&schema["items"], item, &path, violations);
+            }
+        }
+    }
+}
+
+/// Check whether a value has one of the types named by JSON schemas.
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Get the path to a field of an object.
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format;
+    use crate::test_support::{MetadataFixture, TempDir};
+    use serde_json::json;
+
+    #[test]
+    fn generated_documents_match_the_schema() {
+        let dir = TempDir::new();
+        let mut fixture = MetadataFixture::new(dir.path());
+        let root = fixture.package("fixture", "0.1.0");
+        let dependency = fixture.package("dependency", "1.0.0");
+        fixture.member(&root);
+        fixture.depend(&root, &dependency);
+
+        let doc = fixture.build(&[]);
+        validate(&format::json::to_value(&doc)).unwrap();
+    }
+
+    #[test]
+    fn violations_name_the_fields() {
+        let object = json!({
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "dataLicense": "CC0-1.0",
+            "name": "fixture.spdx.json",
+            "spdxVersion": "SPDX-2.3",
+            "creationInfo": { "created": "2022-01-01T00:00:00Z", "creators": [] },
+            "packages": [{ "SPDXID": "SPDXRef-fixture", "name": "fixture" }],
+            "relationships": [{
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBED",
+                "relatedSpdxElement": "SPDXRef-fixture",
+            }],
+        });
+
+        let error = validate(&object).unwrap_err().to_string();
+
+        assert!(error.contains("3 fields are invalid"), "{}", error);
+        assert!(error.contains("creationInfo.creators: expected at least 1 items"));
+        assert!(error.contains("packages[0].downloadLocation: missing"));
+        assert!(error.contains("relationships[0].relationshipType: \"DESCRIBED\""));
+    }
+}
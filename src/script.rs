@@ -0,0 +1,69 @@
+//! Runs post-processing scripts on the document before it's written out.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::io::Write;
+use std::ops::Not as _;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Pass the JSON object of the document through a post-processing script.
+///
+/// The script is given the JSON on stdin, and must print the modified JSON on stdout and
+/// exit successfully. Anything it prints on stderr is shown to the user. If it fails, or
+/// prints something other than a JSON object, nothing is written.
+pub fn post_process(script: &Path, object: &Value) -> Result<Value> {
+    log::info!(
+        target: "cargo_spdx",
+        "post-processing the document with '{}'",
+        script.display()
+    );
+
+    let input = serde_json::to_vec(object)?;
+
+    let mut child = Command::new(script)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("failed to run '{}': {}", script.display(), e))?;
+
+    // The input is written on its own thread, so a script printing output before it's read
+    // all of its input can't deadlock against us.
+    // PANIC SAFETY: The child's stdin was piped above.
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+
+    let output = child.wait_with_output()?;
+
+    // A script may exit without reading all of its input, which isn't an error in itself.
+    // PANIC SAFETY: Writing never panics.
+    if let Err(e) = writer.join().unwrap() {
+        log::debug!(target: "cargo_spdx", "script didn't read all of its input: {}", e);
+    }
+
+    if output.status.success().not() {
+        return Err(anyhow!(
+            "post-processing script '{}' failed: {}",
+            script.display(),
+            output.status
+        ));
+    }
+
+    let object: Value = serde_json::from_slice(&output.stdout).map_err(|e| {
+        anyhow!(
+            "post-processing script '{}' didn't print valid JSON: {}",
+            script.display(),
+            e
+        )
+    })?;
+
+    if object.is_object().not() {
+        return Err(anyhow!(
+            "post-processing script '{}' didn't print a JSON object",
+            script.display()
+        ));
+    }
+
+    Ok(object)
+}
@@ -0,0 +1,260 @@
+//! Fixtures shared by the tests of several modules.
+
+use crate::cargo::CrateMetadata;
+use crate::document::{self, Document};
+use crate::GenerateOptions;
+use cargo_metadata::Package;
+use clap::Parser;
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How many temporary directories this process has made, to keep their names unique.
+static TEMP_DIRS: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary directory, removed along with its contents when it's dropped.
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Make a new, empty temporary directory.
+    pub fn new() -> Self {
+        let count = TEMP_DIRS.fetch_add(1, Ordering::SeqCst);
+        let path = env::temp_dir().join(format!("cargo-spdx-test-{}-{}", process::id(), count));
+
+        // PANIC SAFETY: Tests can't run without somewhere to put their fixtures.
+        fs::create_dir_all(&path).unwrap();
+
+        // Canonicalizing keeps paths comparable when the temporary directory is a symlink.
+        // PANIC SAFETY: The directory was just created.
+        TempDir(fs::canonicalize(path).unwrap())
+    }
+
+    /// Get the path of the directory.
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Write a file into the directory, creating any missing parent directories, and return
+    /// its path.
+    pub fn write(&self, relative: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.0.join(relative);
+
+        // PANIC SAFETY: The path is inside the directory, which can be written to.
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Parse options from the arguments given after `cargo spdx`.
+pub fn options(args: &[&str]) -> GenerateOptions {
+    // PANIC SAFETY: Tests only pass arguments the CLI accepts.
+    GenerateOptions::try_parse_from(std::iter::once("cargo-spdx").chain(args.iter().copied()))
+        .unwrap()
+}
+
+/// Get the ID of a fixture package.
+pub fn package_id(name: &str, version: &str) -> String {
+    format!("{} {} (path+file:///fixture/{})", name, version, name)
+}
+
+/// Get the `cargo metadata` JSON of a package with a library target, whose manifest is in the
+/// directory named after it under `root`.
+pub fn package_json(root: &Path, name: &str, version: &str) -> Value {
+    let dir = root.join(name);
+
+    json!({
+        "name": name,
+        "version": version,
+        "id": package_id(name, version),
+        "source": null,
+        "license": null,
+        "license_file": null,
+        "description": null,
+        "authors": [],
+        "dependencies": [],
+        "targets": [{
+            "name": name,
+            "kind": ["lib"],
+            "crate_types": ["lib"],
+            "required-features": [],
+            "src_path": dir.join("src/lib.rs"),
+            "edition": "2021",
+            "doctest": true,
+            "test": true,
+            "doc": true,
+        }],
+        "features": {},
+        "manifest_path": dir.join("Cargo.toml"),
+        "categories": [],
+        "keywords": [],
+        "edition": "2021",
+        "metadata": null,
+        "links": null,
+        "publish": null,
+        "readme": null,
+        "repository": null,
+        "homepage": null,
+        "documentation": null,
+        "default_run": null,
+        "rust_version": null,
+    })
+}
+
+/// Deserialize a package from its `cargo metadata` JSON.
+pub fn package(json: Value) -> Package {
+    // PANIC SAFETY: Fixtures are built from `package_json`, which has every required field.
+    serde_json::from_value(json).unwrap()
+}
+
+/// The output of `cargo metadata` for a fixture workspace.
+#[derive(Debug)]
+pub struct MetadataFixture {
+    /// The root directory of the workspace.
+    root: PathBuf,
+    /// The JSON of each package.
+    packages: Vec<Value>,
+    /// The IDs of the dependencies of each package, in the same order.
+    dependencies: Vec<Vec<String>>,
+    /// The IDs of the workspace members, the first of which is the root package.
+    members: Vec<String>,
+}
+
+impl MetadataFixture {
+    /// Start a fixture whose workspace is in `root`, with no packages.
+    pub fn new(root: &Path) -> Self {
+        MetadataFixture {
+            root: root.to_owned(),
+            packages: vec![],
+            dependencies: vec![],
+            members: vec![],
+        }
+    }
+
+    /// Add a package, returning its ID.
+    pub fn package(&mut self, name: &str, version: &str) -> String {
+        self.package_with(name, version, |_| {})
+    }
+
+    /// Add a package after changing its JSON, returning its ID.
+    pub fn package_with(
+        &mut self,
+        name: &str,
+        version: &str,
+        edit: impl FnOnce(&mut Value),
+    ) -> String {
+        let mut json = package_json(&self.root, name, version);
+        edit(&mut json);
+
+        let id = json["id"].as_str().unwrap_or_default().to_owned();
+        self.packages.push(json);
+        self.dependencies.push(vec![]);
+        id
+    }
+
+    /// Make a package a member of the workspace. The first member is the root package.
+    pub fn member(&mut self, id: &str) {
+        self.members.push(id.to_owned());
+    }
+
+    /// Make one package depend on another.
+    pub fn depend(&mut self, from: &str, to: &str) {
+        let index = self
+            .packages
+            .iter()
+            .position(|package| package["id"] == from)
+            .unwrap_or_else(|| panic!("no package '{}' in the fixture", from));
+
+        self.dependencies[index].push(to.to_owned());
+    }
+
+    /// Get the `cargo metadata` JSON of the workspace.
+    pub fn to_json(&self) -> Value {
+        let name_of = |id: &str| {
+            self.packages
+                .iter()
+                .find(|package| package["id"] == id)
+                .map(|package| package["name"].clone())
+                .unwrap_or_default()
+        };
+
+        let nodes = self
+            .packages
+            .iter()
+            .zip(&self.dependencies)
+            .map(|(package, dependencies)| {
+                let deps = dependencies
+                    .iter()
+                    .map(|id| json!({ "name": name_of(id), "pkg": id }))
+                    .collect::<Vec<_>>();
+
+                json!({
+                    "id": package["id"],
+                    "dependencies": dependencies,
+                    "deps": deps,
+                    "features": [],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "packages": self.packages,
+            "workspace_members": self.members,
+            "resolve": {
+                "nodes": nodes,
+                "root": self.members.first(),
+            },
+            "workspace_root": self.root,
+            "target_directory": self.root.join("target"),
+            "version": 1,
+            "metadata": null,
+        })
+    }
+
+    /// Write the fixture into its workspace root, and load it with the given options, which
+    /// are returned too.
+    pub fn load(&self, args: &[&str]) -> (GenerateOptions, CrateMetadata) {
+        let path = self.root.join("metadata.json");
+
+        // PANIC SAFETY: The workspace root of a fixture is a temporary directory.
+        fs::write(&path, self.to_json().to_string()).unwrap();
+
+        let path = path.to_string_lossy().into_owned();
+        let mut all_args = vec!["--metadata", path.as_str()];
+        all_args.extend_from_slice(HERMETIC_ARGS);
+        all_args.extend_from_slice(args);
+
+        let options = options(&all_args);
+
+        // PANIC SAFETY: Fixtures always have a root package among the packages.
+        let metadata = CrateMetadata::load(&options).unwrap();
+        (options, metadata)
+    }
+
+    /// Build a document from the fixture with the given options.
+    pub fn build(&self, args: &[&str]) -> Document {
+        let (options, metadata) = self.load(args);
+
+        // PANIC SAFETY: Fixtures are valid metadata, and the options avoid anything external.
+        document::build(&options, "fixture.spdx", &metadata).unwrap()
+    }
+}
+
+/// Arguments which keep building a document from reaching for git or asking questions.
+pub const HERMETIC_ARGS: &[&str] = &[
+    "--host-url",
+    "https://example.com/sboms",
+    "--creator",
+    "Test <test@example.com>",
+    "--no-interact",
+];
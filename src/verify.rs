@@ -0,0 +1,92 @@
+//! Checks a previously generated SBOM against the current state of the crate.
+
+use crate::document::read_package_versions;
+use crate::{generate_sbom, ErrorKind, GenerateOptions};
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// The versions of each package, keyed on the package name.
+pub type Versions = BTreeMap<String, BTreeSet<String>>;
+
+/// Check the SBOM at `path` has the same packages, at the same versions, as a freshly
+/// generated one.
+///
+/// Returns an error listing every package added, removed, or changed since the SBOM was
+/// generated.
+pub fn verify_sbom(options: &GenerateOptions, path: &Path) -> Result<()> {
+    log::info!(target: "cargo_spdx", "verifying '{}'", path.display());
+
+    let existing = collect_versions(read_package_versions(path)?);
+
+    let doc = generate_sbom(options, None)?;
+    let current = collect_versions(doc.package_information.iter().map(|package| {
+        (
+            package.package_name.clone(),
+            package.package_version.clone().unwrap_or_default(),
+        )
+    }));
+
+    let differences = diff_versions(&existing, &current);
+
+    if differences.is_empty() {
+        log::info!(target: "cargo_spdx", "'{}' is up to date", path.display());
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "'{}' is out of date, {} packages differ:\n  {}",
+        path.display(),
+        differences.len(),
+        differences.join("\n  ")
+    )
+    .context(ErrorKind::Validation))
+}
+
+/// Group package versions by package name.
+pub fn collect_versions(packages: impl IntoIterator<Item = (String, String)>) -> Versions {
+    let mut versions = Versions::new();
+
+    for (name, version) in packages {
+        versions.entry(name).or_default().insert(version);
+    }
+
+    versions
+}
+
+/// Describe the packages which were added, removed, or changed version.
+pub fn diff_versions(existing: &Versions, current: &Versions) -> Vec<String> {
+    let empty = BTreeSet::new();
+    let names = existing
+        .keys()
+        .chain(current.keys())
+        .collect::<BTreeSet<_>>();
+    let mut differences = vec![];
+
+    for name in names {
+        let before = existing.get(name).unwrap_or(&empty);
+        let after = current.get(name).unwrap_or(&empty);
+
+        let removed = before.difference(after).collect::<Vec<_>>();
+        let added = after.difference(before).collect::<Vec<_>>();
+
+        // A single version swapped for another is reported as a change.
+        if let ([old], [new]) = (removed.as_slice(), added.as_slice()) {
+            differences.push(format!("changed: {} {} -> {}", name, old, new));
+            continue;
+        }
+
+        differences.extend(
+            removed
+                .into_iter()
+                .map(|version| format!("removed: {} {}", name, version)),
+        );
+        differences.extend(
+            added
+                .into_iter()
+                .map(|version| format!("added: {} {}", name, version)),
+        );
+    }
+
+    differences
+}